@@ -3,22 +3,65 @@ use std::io;
 use std::io::Write;
 use std::time::Instant;
 use crate::lang::LineResult::*;
-use crate::lang::run_line;
+use crate::lang::{derive_expression, plot_expression, run_line, simplify_expression, table_expression};
+use crate::lang::random::seed;
+use crate::lang::backend::{select, Backend};
+use crate::lang::value::Value;
 
-pub fn cli(memory: &mut HashMap<String, f64>) {
+pub fn cli(memory: &mut HashMap<String, Value>) {
     println!("Malors CLI launched");
-    println!("Commands: $m to print heap | $q to quit");
+    println!("Commands: $m to print heap | $simplify <expr> to simplify an expression | $derive <var> <expr> to derive an expression | plot y = <expr> for x in <a>..<b> to draw it | table <expr>, ... for x in <a>..<b> step <s> to tabulate (table --csv for CSV) | $seed <n> to seed rand() | $backend double|single|fixed|calculator|decimal:<digits> to change the arithmetic | $q to quit");
     loop {
         print!(">>> ");
         let mut input = String::new();
         io::stdout().flush().expect("Failed to flush stdout");
-        io::stdin().read_line(&mut input).expect("INTERNAL-ERROR: Failed to get user input");
-        if input.trim() == "$q" {
+        let read = io::stdin().read_line(&mut input).expect("INTERNAL-ERROR: Failed to get user input");
+        if read == 0 || input.trim() == "$q" {
             break;
         } else if input.trim().is_empty() {
             // Do nothing
         } else if input.trim() == "$m" {
             println!("Memory state:\n {:?}", memory.clone())
+        } else if let Some(expression) = input.trim().strip_prefix("$simplify ") {
+            match simplify_expression(expression) {
+                Ok(simplified) => println!("{}", simplified),
+                Err(err) => println!("\x1b[31mPROGRAM ERROR:\x1b[0m \n{}", err),
+            }
+        } else if let Some(arguments) = input.trim().strip_prefix("$derive ") {
+            let (var, expression) = arguments.trim().split_once(' ').unwrap_or((arguments.trim(), ""));
+            match derive_expression(var, expression, memory) {
+                Ok((derivative, value)) => {
+                    println!("d/d{} = {}", var, derivative);
+                    if let (Some(value), Some(at)) = (value, memory.get(var)) {
+                        println!("    = {} at {} = {}", value, var, at);
+                    }
+                }
+                Err(err) => println!("\x1b[31mPROGRAM ERROR:\x1b[0m \n{}", err),
+            }
+        } else if let Some(argument) = input.trim().strip_prefix("$seed ") {
+            match argument.trim().parse::<u64>() {
+                Ok(n) => seed(n),
+                Err(_) => println!("\x1b[31mPROGRAM ERROR:\x1b[0m \nThe seed must be a positive integer: {}", argument.trim()),
+            }
+        } else if let Some(argument) = input.trim().strip_prefix("$backend ") {
+            match Backend::parse(argument.trim()) {
+                Ok(backend) => select(backend),
+                Err(err) => println!("\x1b[31mPROGRAM ERROR:\x1b[0m \n{}", err),
+            }
+        } else if let Some(arguments) = input.trim().strip_prefix("plot ") {
+            match plot_expression(arguments, memory) {
+                Ok(graph) => println!("{}", graph),
+                Err(err) => println!("\x1b[31mPROGRAM ERROR:\x1b[0m \n{}", err),
+            }
+        } else if let Some(arguments) = input.trim().strip_prefix("table ") {
+            let (csv, arguments) = match arguments.trim().strip_prefix("--csv ") {
+                Some(arguments) => (true, arguments),
+                None => (false, arguments),
+            };
+            match table_expression(arguments, memory, csv) {
+                Ok(table) => println!("{}", table),
+                Err(err) => println!("\x1b[31mPROGRAM ERROR:\x1b[0m \n{}", err),
+            }
         } else {
             let start_time = Instant::now();
             let result = run_line(input.as_str(), memory);
@@ -30,7 +73,7 @@ pub fn cli(memory: &mut HashMap<String, f64>) {
             }
             let end_time = Instant::now();
             let elapsed_time = end_time - start_time;
-            memory.insert("_ms".into(), elapsed_time.as_millis() as f64);
+            memory.insert("_ms".into(), Value::Number(elapsed_time.as_millis() as f64));
         }
     }
     println!("The End.")
@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::io::Write;
+use crate::lang::LineResult::*;
+use crate::lang::{check_condition, parse_line, run_line, LineResult};
+use crate::lang::line_type::LineType;
+use crate::lang::runner::{run_with, Observer};
+use crate::lang::value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    Step,     // Pause before every line, including the lines inside if/while bodies
+    Next,     // Pause before every script line
+    Continue, // Pause only on breakpoints
+}
+
+struct Debugger {
+    // Script line number => optional condition written as a Malors comparison
+    breakpoints: BTreeMap<usize, Option<String>>,
+    mode: Mode,
+    quit: bool,
+}
+
+pub fn debugger(script: &str, memory: &mut HashMap<String, Value>) {
+    println!("Malors debugger launched");
+    println!("Commands: $s step | $n next line | $c continue | $b <line> [condition] add breakpoint | $d <line> delete breakpoint | $l list breakpoints | $m print heap | $q quit");
+    println!("Any other input is run as a Malors line, empty input steps");
+    let mut debugger = Debugger { breakpoints: BTreeMap::new(), mode: Mode::Step, quit: false };
+
+    for (i, line) in script.lines().enumerate() {
+        let number = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if debugger.mode != Mode::Continue || debugger.hit(number, memory) {
+            debugger.pause(&format!("{}", number), line, memory);
+        }
+        if debugger.quit {
+            break;
+        }
+        match parse_line(line) {
+            Ok(line_type) => debugger.execute(number, line, line_type, memory),
+            Err(err) => report(number, Err(err)),
+        }
+        if debugger.quit {
+            break;
+        }
+    }
+    println!("The End.")
+}
+
+impl Debugger {
+    fn execute(&mut self, number: usize, line: &str, line_type: LineType, memory: &mut HashMap<String, Value>) {
+        // Bodies are split on colons by the parser, split the source the same way to display them
+        let sources: Vec<&str> = line.split(':').skip(1).map(|s| s.trim()).collect();
+        let mut observer = LineObserver { debugger: self, number, line, sources };
+        let result = run_with(memory, line_type, &mut observer);
+        report(number, result);
+    }
+
+    // Check whether a breakpoint is set on the line and its condition (if any) holds
+    fn hit(&self, number: usize, memory: &mut HashMap<String, Value>) -> bool {
+        match self.breakpoints.get(&number) {
+            None => false,
+            Some(None) => true,
+            Some(Some(condition)) => match check_condition(condition, memory) {
+                Ok(hit) => hit,
+                Err(err) => {
+                    println!("\x1b[31mBREAKPOINT ERROR (line {}):\x1b[0m \n{}", number, err);
+                    true
+                }
+            },
+        }
+    }
+
+    fn pause(&mut self, location: &str, line: &str, memory: &mut HashMap<String, Value>) {
+        println!("\x1b[33m[line {}]\x1b[0m {}", location, line.trim());
+        loop {
+            print!("(debug) ");
+            let mut input = String::new();
+            io::stdout().flush().expect("Failed to flush stdout");
+            if io::stdin().read_line(&mut input).expect("INTERNAL-ERROR: Failed to get user input") == 0 {
+                // End of input, nobody is left to drive the session
+                self.quit = true;
+                return;
+            }
+            let input = input.trim();
+            let (command, argument) = match input.split_once(' ') {
+                None => (input, ""),
+                Some((command, argument)) => (command, argument.trim()),
+            };
+            match command {
+                "" | "$s" => {
+                    self.mode = Mode::Step;
+                    return;
+                }
+                "$n" => {
+                    self.mode = Mode::Next;
+                    return;
+                }
+                "$c" => {
+                    self.mode = Mode::Continue;
+                    return;
+                }
+                "$q" => {
+                    self.quit = true;
+                    return;
+                }
+                "$b" => {
+                    let (number, condition) = match argument.split_once(' ') {
+                        None => (argument, None),
+                        Some((number, condition)) => (number, Some(condition.trim().to_string())),
+                    };
+                    match number.parse::<usize>() {
+                        Ok(number) => {
+                            self.breakpoints.insert(number, condition);
+                        }
+                        Err(_) => println!("Usage: $b <line> [condition]"),
+                    }
+                }
+                "$d" => {
+                    match argument.parse::<usize>() {
+                        Ok(number) => {
+                            if self.breakpoints.remove(&number).is_none() {
+                                println!("No breakpoint on line {}", number)
+                            }
+                        }
+                        Err(_) => println!("Usage: $d <line>"),
+                    }
+                }
+                "$l" => {
+                    for (number, condition) in &self.breakpoints {
+                        match condition {
+                            None => println!("line {}", number),
+                            Some(condition) => println!("line {} when {}", number, condition),
+                        }
+                    }
+                }
+                "$m" => println!("Memory state:\n {:?}", memory.clone()),
+                _ => {
+                    let result = run_line(input, memory);
+                    if let Ok(Output(string)) = result {
+                        println!("{}", string)
+                    } else if let Err(err) = result {
+                        println!("\x1b[31mPROGRAM ERROR:\x1b[0m \n{}", err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The if and while bodies are run by the runner, the debugger pauses before their lines
+struct LineObserver<'a> {
+    debugger: &'a mut Debugger,
+    number: usize,
+    line: &'a str,
+    sources: Vec<&'a str>,
+}
+
+impl Observer for LineObserver<'_> {
+    fn iteration(&mut self, iteration: usize, memory: &mut HashMap<String, Value>) -> bool {
+        // The first iteration already paused on the line itself
+        if self.debugger.mode == Mode::Continue && self.debugger.hit(self.number, memory) {
+            self.debugger.pause(&format!("{} (iteration {})", self.number, iteration), self.line, memory);
+        }
+        !self.debugger.quit
+    }
+
+    fn action(&mut self, index: usize, memory: &mut HashMap<String, Value>) -> bool {
+        if self.debugger.mode == Mode::Step {
+            self.debugger.pause(&format!("{}.{}", self.number, index + 1), self.sources.get(index).unwrap_or(&""), memory);
+        }
+        !self.debugger.quit
+    }
+
+    fn report(&mut self, result: Result<LineResult, String>) {
+        report(self.number, result)
+    }
+}
+
+fn report(number: usize, result: Result<LineResult, String>) {
+    if let Ok(Output(string)) = result {
+        println!("{}", string)
+    } else if let Err(err) = result {
+        println!("\x1b[31mPROGRAM ERROR (line {}):\x1b[0m \n{}", number, err);
+        println!("<Instruction skipped>");
+    }
+}
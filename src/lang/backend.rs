@@ -0,0 +1,185 @@
+/*
+Numeric backends of the arithmetic, selected for the whole session with $backend or --backend
+or with select() when Malors is used as a library.
+The operators and functions convert their f64 operands to the Numeric of the backend, compute with it
+and store the result back as an f64, so the tokenizer, the parser and the memory are shared by all the backends.
+The backend is a global of the thread and not a parameter of the evaluation: two backends can't run at once in a thread,
+and Fixed and Calculator lose the digits an f64 can't hold between two operations.
+Calculator emulates the 14 significant decimal digits of the TI and Casio calculators and displays 10 of them:
+0.1 + 0.2 is 0.3, 2/3 is 0.6666666667 and 1/3*3 is 1 like on a calculator.
+Decimal computes with the given number of digits, its numbers don't fit in an f64:
+the tokenizer gives decimals and they are stored as Value::Decimal
+ */
+
+use std::cell::Cell;
+use crate::lang::numeric::{CalculatorDecimal, Fixed, Numeric};
+use crate::lang::tokenizer::{Function, Operator};
+
+// Digits of "decimal" without a precision
+pub const DECIMAL_DIGITS: usize = 50;
+const MAX_DECIMAL_DIGITS: usize = 10000;
+// Digits shown by the calculators, the other ones only round the results
+const CALCULATOR_DISPLAY_DIGITS: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    Double,
+    Single,
+    Fixed,
+    Calculator,
+    Decimal(usize),
+}
+
+thread_local! {
+    static BACKEND: Cell<Backend> = const { Cell::new(Backend::Double) };
+}
+
+pub fn select(backend: Backend) {
+    BACKEND.with(|selected| selected.set(backend));
+}
+
+pub fn selected() -> Backend {
+    BACKEND.with(|selected| selected.get())
+}
+
+impl Backend {
+    pub fn parse(name: &str) -> Result<Backend, String> {
+        match name {
+            "double" | "f64" => Ok(Backend::Double),
+            "single" | "f32" => Ok(Backend::Single),
+            "fixed" => Ok(Backend::Fixed),
+            "calculator" => Ok(Backend::Calculator),
+            "decimal" => Ok(Backend::Decimal(DECIMAL_DIGITS)),
+            // "decimal:100"
+            _ => match name.strip_prefix("decimal:").map(|digits| digits.parse::<usize>()) {
+                Some(Ok(digits)) if (1..=MAX_DECIMAL_DIGITS).contains(&digits) => Ok(Backend::Decimal(digits)),
+                Some(_) => Err(format!("The precision of decimal must be from 1 to {} digits: {}", MAX_DECIMAL_DIGITS, name)),
+                None => Err(format!("Unknown backend {}, expected double, single, fixed, calculator or decimal:<digits>", name)),
+            },
+        }
+    }
+
+    pub fn decimal_digits(&self) -> usize {
+        match self {
+            Backend::Decimal(digits) => *digits,
+            _ => DECIMAL_DIGITS,
+        }
+    }
+
+    pub fn operate(&self, o: &Operator, a: f64, b: f64) -> Result<f64, String> {
+        // Infinities and NaN only exist in f64
+        if !a.is_finite() || !b.is_finite() {
+            return o.compute(a, b)
+        }
+        match self {
+            // The floats of lists and matrices stay f64 with the decimals
+            Backend::Double | Backend::Decimal(_) => o.compute(a, b),
+            Backend::Single => operate::<f32>(o, a, b),
+            Backend::Fixed => operate::<Fixed>(o, a, b),
+            Backend::Calculator => operate::<CalculatorDecimal>(o, a, b),
+        }
+    }
+
+    pub fn apply(&self, function: &Function, x: f64) -> Result<f64, String> {
+        if !x.is_finite() {
+            return function.apply_number(x)
+        }
+        match self {
+            Backend::Double | Backend::Decimal(_) => function.apply_number(x),
+            Backend::Single => apply::<f32>(function, x),
+            Backend::Fixed => apply::<Fixed>(function, x),
+            Backend::Calculator => apply::<CalculatorDecimal>(function, x),
+        }
+    }
+
+    // A number of the script, kept to the digits of the backend
+    pub fn round(&self, x: f64) -> Result<f64, String> {
+        if !x.is_finite() {
+            return Ok(x)
+        }
+        match self {
+            Backend::Double | Backend::Decimal(_) => Ok(x),
+            Backend::Single => Ok(f32::from_f64(x)?.to_f64()),
+            Backend::Fixed => Ok(Fixed::from_f64(x)?.to_f64()),
+            Backend::Calculator => Ok(CalculatorDecimal::from_f64(x)?.to_f64()),
+        }
+    }
+
+    // Text of a number printed by the script or the heap
+    pub fn display(&self, x: f64) -> String {
+        if !x.is_finite() {
+            return x.to_string()
+        }
+        match self {
+            // The shortest text reading back as the same f32: 2/3 is 0.6666667
+            Backend::Single => (x as f32).to_string(),
+            Backend::Calculator => format!("{:.*e}", CALCULATOR_DISPLAY_DIGITS - 1, x).parse::<f64>().unwrap().to_string(),
+            _ => x.to_string(),
+        }
+    }
+}
+
+fn operate<N: Numeric>(o: &Operator, a: f64, b: f64) -> Result<f64, String> {
+    Ok(o.compute(N::from_f64(a)?, N::from_f64(b)?)?.to_f64())
+}
+
+fn apply<N: Numeric>(function: &Function, x: f64) -> Result<f64, String> {
+    Ok(function.apply_number(N::from_f64(x)?)?.to_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+    use crate::lang::{run_line, LineResult};
+
+    // Selects a backend until it's dropped, Double is back even when a test fails
+    struct Selected;
+
+    impl Selected {
+        fn new(backend: Backend) -> Selected {
+            select(backend);
+            Selected
+        }
+    }
+
+    impl Drop for Selected {
+        fn drop(&mut self) {
+            select(Backend::Double);
+        }
+    }
+
+    // Output of the last line of the script with the backend
+    fn output(backend: Backend, script: &str) -> String {
+        let _selected = Selected::new(backend);
+        let mut memory = HashMap::new();
+        let mut last = LineResult::Nothing;
+        for line in script.lines() {
+            last = run_line(line, &mut memory).unwrap();
+        }
+        match last {
+            LineResult::Output(output) => output,
+            LineResult::Nothing => panic!("No output for {}", script),
+        }
+    }
+
+    #[test]
+    fn displays_the_digits_of_the_calculator() {
+        assert_eq!(output(Backend::Calculator, "a = 1/3*3\nb = 2/3\nc = 0.1 + 0.2\na: b: c"), ":1:0.6666666667:0.3");
+        assert_eq!(output(Backend::Calculator, "l = [2/3, 1/3*3]\nl"), ":[0.6666666667, 1]");
+        assert_eq!(output(Backend::Calculator, "a = 123456789012345\na"), ":123456789000000");
+        assert_eq!(output(Backend::Double, "a = 1/3*3\nb = 2/3\na: b"), ":1:0.6666666666666666");
+    }
+
+    #[test]
+    fn keeps_the_digits_of_the_calculator_in_the_computations() {
+        // 1/3*3 displays 1 but 1 - 1/3*3 shows the 14th digit
+        assert_eq!(output(Backend::Calculator, "a = 1 - 1/3*3\na"), ":0.00000000000001");
+    }
+
+    #[test]
+    fn displays_the_floats_of_single() {
+        assert_eq!(output(Backend::Single, "a = 2/3\nb = 0.1 + 0.2\nc = 16777217\na: b: c"), ":0.6666667:0.3:16777216");
+        assert_eq!(output(Backend::Single, "m = [[1, 2], [3, 4]] / 3\nm"), ":\n[0.33333334  0.6666667]\n[         1  1.3333334]");
+    }
+}
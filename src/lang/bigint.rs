@@ -0,0 +1,530 @@
+/*
+Integers of any size for the exact integers and the decimal numbers, stored as limbs of 9 decimal digits from the lowest one.
+Only what the arithmetic needs: + - * / %, powers, factorials, comparisons, the decimal digits
+and the bitwise operations, computed on the two's complement like the integers of Python
+ */
+
+use std::cmp::Ordering;
+use std::fmt;
+
+const BASE: u64 = 1_000_000_000;
+const LIMB_DIGITS: usize = 9;
+// Words of the two's complement in the bitwise operations
+const WORD: u64 = 1 << 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>, // Lowest first, without zero limbs on top, empty for 0
+}
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt { negative: false, limbs: vec![] }
+    }
+
+    pub fn from_i128(x: i128) -> BigInt {
+        let mut magnitude = x.unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude > 0 {
+            limbs.push((magnitude % BASE as u128) as u32);
+            magnitude /= BASE as u128;
+        }
+        BigInt::new(x < 0, limbs)
+    }
+
+    // "-123456789012345678901234567890", None if it isn't only digits
+    pub fn parse(text: &str) -> Option<BigInt> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None
+        }
+        let mut limbs = Vec::new();
+        let mut end = digits.len();
+        while end > 0 {
+            let start = end.saturating_sub(LIMB_DIGITS);
+            limbs.push(digits[start..end].parse::<u32>().ok()?);
+            end = start;
+        }
+        Some(BigInt::new(negative, limbs))
+    }
+
+    pub fn pow10(n: usize) -> BigInt {
+        let mut limbs = vec![0; n / LIMB_DIGITS];
+        limbs.push(10u32.pow((n % LIMB_DIGITS) as u32));
+        BigInt::new(false, limbs)
+    }
+
+    fn new(negative: bool, mut limbs: Vec<u32>) -> BigInt {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigInt { negative: negative && !limbs.is_empty(), limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn negate(&self) -> BigInt {
+        BigInt::new(!self.negative, self.limbs.clone())
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::new(false, self.limbs.clone())
+    }
+
+    // Number of decimal digits, 1 for 0
+    pub fn digits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => (self.limbs.len() - 1) * LIMB_DIGITS + top.to_string().len(),
+            None => 1,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse::<f64>().unwrap()
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_string().parse::<i64>().ok()
+    }
+
+    pub fn add(&self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add_magnitudes(&self.limbs, &other.limbs))
+        }
+        // Opposite signs, the larger magnitude gives the sign
+        match compare_magnitudes(&self.limbs, &other.limbs) {
+            Ordering::Less => BigInt::new(other.negative, subtract_magnitudes(&other.limbs, &self.limbs)),
+            _ => BigInt::new(self.negative, subtract_magnitudes(&self.limbs, &other.limbs)),
+        }
+    }
+
+    pub fn subtract(&self, other: &BigInt) -> BigInt {
+        self.add(&other.negate())
+    }
+
+    pub fn multiply(&self, other: &BigInt) -> BigInt {
+        if self.is_zero() || other.is_zero() {
+            return BigInt::zero()
+        }
+        let mut limbs = vec![0u64; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let product = limbs[i + j] + *a as u64 * *b as u64 + carry;
+                limbs[i + j] = product % BASE;
+                carry = product / BASE;
+            }
+            limbs[i + other.limbs.len()] += carry;
+        }
+        BigInt::new(self.negative != other.negative, limbs.into_iter().map(|limb| limb as u32).collect())
+    }
+
+    // Quotient toward zero and remainder of the sign of self
+    pub fn divide(&self, other: &BigInt) -> Result<(BigInt, BigInt), String> {
+        if other.is_zero() {
+            return Err(format!("Division by zero: {} / 0", self))
+        }
+        Ok(self.truncated_division(other))
+    }
+
+    // self / 10^n like divide, 10^n is never zero
+    pub fn divide_pow10(&self, n: usize) -> (BigInt, BigInt) {
+        self.truncated_division(&BigInt::pow10(n))
+    }
+
+    fn truncated_division(&self, other: &BigInt) -> (BigInt, BigInt) {
+        let (quotient, remainder) = divide_magnitudes(&self.limbs, &other.limbs);
+        (BigInt::new(self.negative != other.negative, quotient), BigInt::new(self.negative, remainder))
+    }
+
+    pub fn pow(&self, exponent: u32) -> BigInt {
+        let mut result = BigInt::from_i128(1);
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.multiply(&base);
+            }
+        }
+        result
+    }
+
+    pub fn factorial(n: u32) -> BigInt {
+        let mut limbs = vec![1];
+        for k in 2..=n as u64 {
+            limbs = multiply_small(&limbs, k);
+            if limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+        }
+        BigInt::new(false, limbs)
+    }
+
+    // Bits of the two's complement: -1 is ...111, the words are followed by copies of the sign word
+    pub fn bitwise(&self, other: &BigInt, operation: fn(u32, u32) -> u32) -> BigInt {
+        let (a, b) = (self.words(), other.words());
+        let length = a.len().max(b.len()) + 1;
+        let extended = |words: &[u32], negative: bool, i: usize| *words.get(i).unwrap_or(if negative { &u32::MAX } else { &0 });
+        let words: Vec<u32> = (0..length)
+            .map(|i| operation(extended(&a, self.negative, i), extended(&b, other.negative, i)))
+            .collect();
+        let negative = words[length - 1] >> 31 == 1;
+        if !negative {
+            return BigInt::new(false, from_words(&words))
+        }
+        // -x is !x + 1
+        let inverted: Vec<u32> = words.iter().map(|word| !word).collect();
+        BigInt::new(true, add_magnitudes(&from_words(&inverted), &[1]))
+    }
+
+    // self * 2^bits
+    pub fn shift_left(&self, bits: u32) -> BigInt {
+        self.multiply(&BigInt::from_i128(2).pow(bits))
+    }
+
+    // Rounded down like the integers: -5 >> 1 is -3
+    pub fn shift_right(&self, bits: u32) -> BigInt {
+        // Past the last bit only the sign is left
+        if bits as usize > self.digits() * 4 {
+            return BigInt::from_i128(if self.negative { -1 } else { 0 })
+        }
+        let (quotient, remainder) = self.truncated_division(&BigInt::from_i128(2).pow(bits));
+        if remainder.is_negative() {
+            return quotient.subtract(&BigInt::from_i128(1))
+        }
+        quotient
+    }
+
+    // Words of the two's complement from the lowest one, without the sign words on top
+    fn words(&self) -> Vec<u32> {
+        let mut magnitude = if self.negative { subtract_magnitudes(&self.limbs, &[1]) } else { self.limbs.clone() };
+        let mut words = Vec::new();
+        while magnitude.iter().any(|limb| *limb != 0) {
+            let (quotient, word) = divide_small(&magnitude, WORD);
+            words.push(if self.negative { !(word as u32) } else { word as u32 });
+            magnitude = quotient;
+        }
+        words
+    }
+
+    // Integer square root, rounded down, of a positive number
+    pub fn sqrt(&self) -> BigInt {
+        if self.is_zero() {
+            return BigInt::zero()
+        }
+        // Newton's method from above: 10^ceil(digits / 2) is larger than the root
+        let mut x = BigInt::pow10(self.digits().div_ceil(2));
+        let two = BigInt::from_i128(2);
+        loop {
+            let y = x.add(&self.truncated_division(&x).0).truncated_division(&two).0;
+            if y >= x {
+                return x
+            }
+            x = y;
+        }
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        limbs.push((sum % BASE) as u32);
+        carry = sum / BASE;
+    }
+    limbs.push(carry as u32);
+    limbs
+}
+
+// a - b with a >= b
+fn subtract_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, limb) in a.iter().enumerate() {
+        let mut difference = *limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += BASE as i64;
+            borrow = 1;
+        }
+        limbs.push(difference as u32);
+    }
+    limbs
+}
+
+fn multiply_small(a: &[u32], factor: u64) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0;
+    for limb in a {
+        let product = *limb as u64 * factor + carry;
+        limbs.push((product % BASE) as u32);
+        carry = product / BASE;
+    }
+    limbs.push(carry as u32);
+    limbs
+}
+
+fn divide_small(a: &[u32], divisor: u64) -> (Vec<u32>, u64) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder = 0;
+    for i in (0..a.len()).rev() {
+        let current = remainder * BASE + a[i] as u64;
+        quotient[i] = (current / divisor) as u32;
+        remainder = current % divisor;
+    }
+    (quotient, remainder)
+}
+
+// Limbs of the number written with the words, from the lowest ones
+fn from_words(words: &[u32]) -> Vec<u32> {
+    let mut limbs = vec![];
+    for word in words.iter().rev() {
+        limbs = multiply_small(&limbs, WORD);
+        limbs = add_magnitudes(&limbs, &[*word % BASE as u32, *word / BASE as u32]);
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+    }
+    limbs
+}
+
+// Long division of Knuth (The Art of Computer Programming, 4.3.1 algorithm D) in base 10^9
+fn divide_magnitudes(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if compare_magnitudes(a, b) == Ordering::Less {
+        return (vec![], a.to_vec())
+    }
+    if b.len() == 1 {
+        let (quotient, remainder) = divide_small(a, b[0] as u64);
+        return (quotient, vec![remainder as u32])
+    }
+    // Scaled so the top limb of the divisor is at least BASE / 2, the estimations are then off by 2 at most
+    let factor = BASE / (b[b.len() - 1] as u64 + 1);
+    let mut u = multiply_small(a, factor);
+    let mut v = multiply_small(b, factor);
+    v.pop();
+    let n = v.len();
+    let mut quotient = vec![0; u.len() - n];
+    for j in (0..u.len() - n).rev() {
+        let top = u[j + n] as u64 * BASE + u[j + n - 1] as u64;
+        let mut estimate = top / v[n - 1] as u64;
+        let mut rest = top % v[n - 1] as u64;
+        while estimate >= BASE || estimate * v[n - 2] as u64 > rest * BASE + u[j + n - 2] as u64 {
+            estimate -= 1;
+            rest += v[n - 1] as u64;
+            if rest >= BASE {
+                break
+            }
+        }
+        // u[j..=j + n] -= estimate * v
+        let mut borrow = 0;
+        let mut carry = 0;
+        for i in 0..n {
+            let product = estimate * v[i] as u64 + carry;
+            carry = product / BASE;
+            let mut difference = u[i + j] as i64 - (product % BASE) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += BASE as i64;
+                borrow = 1;
+            }
+            u[i + j] = difference as u32;
+        }
+        let difference = u[j + n] as i64 - carry as i64 - borrow;
+        if difference < 0 {
+            // The estimation was one too large, v is added back
+            u[j + n] = (difference + BASE as i64) as u32;
+            estimate -= 1;
+            let mut carry = 0;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = (sum % BASE) as u32;
+                carry = sum / BASE;
+            }
+            u[j + n] = ((u[j + n] as u64 + carry) % BASE) as u32;
+        } else {
+            u[j + n] = difference as u32;
+        }
+        quotient[j] = estimate as u32;
+    }
+    let (remainder, _) = divide_small(&u[..n], factor);
+    (quotient, remainder)
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.limbs, &other.limbs),
+            (true, true) => compare_magnitudes(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut text = if self.negative { "-".to_string() } else { String::new() };
+        match self.limbs.split_last() {
+            Some((top, rest)) => {
+                text.push_str(&top.to_string());
+                for limb in rest.iter().rev() {
+                    text.push_str(&format!("{:09}", limb));
+                }
+            }
+            None => text.push('0'),
+        }
+        write!(f, "{}", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(text: &str) -> BigInt {
+        BigInt::parse(text).unwrap()
+    }
+
+    fn divide(a: &str, b: &str) -> (String, String) {
+        let (quotient, remainder) = big(a).divide(&big(b)).unwrap();
+        (quotient.to_string(), remainder.to_string())
+    }
+
+    #[test]
+    fn parses_and_prints_across_the_limbs() {
+        for text in ["0", "7", "999999999", "1000000000", "1000000001", "-123456789012345678901234567890"] {
+            assert_eq!(big(text).to_string(), text);
+        }
+        assert_eq!(big("-0"), BigInt::zero());
+        assert_eq!(big("000012"), BigInt::from_i128(12));
+        assert!(BigInt::parse("12a").is_none());
+        assert!(BigInt::parse("-").is_none());
+    }
+
+    #[test]
+    fn writes_the_powers_of_ten_at_the_limb_boundaries() {
+        for n in [0, 1, 8, 9, 10, 17, 18, 19, 27, 36] {
+            let power = BigInt::pow10(n);
+            assert_eq!(power.to_string(), format!("1{}", "0".repeat(n)));
+            assert_eq!(power.digits(), n + 1);
+            assert_eq!(power.subtract(&BigInt::from_i128(1)).digits(), n.max(1));
+        }
+        assert_eq!(BigInt::pow10(27).divide(&BigInt::pow10(9)).unwrap(), (BigInt::pow10(18), BigInt::zero()));
+        assert_eq!(big("123456789123456789").divide_pow10(9), (big("123456789"), big("123456789")));
+        assert_eq!(divide("999999999999999999999999999", "1000000000000000001"), ("999999999".to_string(), "999999999000000000".to_string()));
+    }
+
+    #[test]
+    fn adds_and_subtracts_with_the_carries() {
+        assert_eq!(big("999999999999999999").add(&BigInt::from_i128(1)), BigInt::pow10(18));
+        assert_eq!(BigInt::pow10(18).subtract(&BigInt::from_i128(1)), big("999999999999999999"));
+        assert_eq!(big("5").subtract(&big("1000000000000")), big("-999999999995"));
+        assert_eq!(big("-1000000000").add(&big("1000000000")), BigInt::zero());
+        assert!(!big("-1000000000").add(&big("1000000000")).is_negative());
+    }
+
+    #[test]
+    fn multiplies_with_the_signs() {
+        assert_eq!(big("999999999999999999").multiply(&big("999999999999999999")), big("999999999999999998000000000000000001"));
+        assert_eq!(big("-12345678901").multiply(&big("98765432109")), big("-1219326311336229232209"));
+        assert_eq!(big("-3").multiply(&big("-4")), big("12"));
+        assert_eq!(big("-3").multiply(&BigInt::zero()), BigInt::zero());
+        assert_eq!(BigInt::from_i128(2).pow(200).to_string(), "1606938044258990275541962092341162602522202993782792835301376");
+        assert_eq!(BigInt::factorial(25).to_string(), "15511210043330985984000000");
+    }
+
+    #[test]
+    fn corrects_the_estimated_quotient_digit() {
+        // The two top limbs of the divisor make the first estimation too large
+        assert_eq!(divide("999999999000000000999999999000000000", "999999999999999999"), ("999999999000000001".to_string(), "999999998000000001".to_string()));
+        assert_eq!(divide("1606938044258990275541962092341162602522202993782792835301376", "717897987691852588770249"),
+            ("2238393297946874000179418290327143433".to_string(), "249667313308346329176559".to_string()));
+    }
+
+    #[test]
+    fn adds_back_the_divisor_when_the_estimation_is_one_too_large() {
+        assert_eq!(divide("999999999999999999999999999500000000", "500000000500000000999999999"), ("1999999997".to_string(), "499999999500000004499999997".to_string()));
+        assert_eq!(divide("191574727000000000000000000503036743", "500000000000000000999999999"), ("383149453".to_string(), "499999999616850547886186196".to_string()));
+    }
+
+    #[test]
+    fn divides_toward_zero_with_the_sign_of_the_dividend() {
+        assert_eq!(divide("-7", "2"), ("-3".to_string(), "-1".to_string()));
+        assert_eq!(divide("7", "-2"), ("-3".to_string(), "1".to_string()));
+        assert_eq!(divide("-7", "-2"), ("3".to_string(), "-1".to_string()));
+        assert_eq!(divide("-1000000000000000000001", "1000000000"), ("-1000000000000".to_string(), "-1".to_string()));
+        assert_eq!(divide("5", "1000000000000"), ("0".to_string(), "5".to_string()));
+    }
+
+    #[test]
+    fn rejects_a_zero_divisor() {
+        assert!(big("12").divide(&BigInt::zero()).is_err());
+        assert!(BigInt::zero().divide(&BigInt::zero()).is_err());
+    }
+
+    #[test]
+    fn gives_the_integer_square_root() {
+        assert_eq!(BigInt::pow10(60).multiply(&BigInt::from_i128(2)).sqrt().to_string(), "1414213562373095048801688724209");
+        assert_eq!(big("999999999999999999").sqrt(), big("999999999"));
+        assert_eq!(BigInt::pow10(18).sqrt(), BigInt::pow10(9));
+        assert_eq!(big("15").sqrt(), big("3"));
+        assert_eq!(BigInt::zero().sqrt(), BigInt::zero());
+    }
+
+    #[test]
+    fn computes_the_bitwise_operations_on_the_twos_complement() {
+        let (a, b) = (big("-123456789012345678901234567890"), big("987654321098765432109876543210"));
+        assert_eq!(a.bitwise(&b, |x, y| x & y), big("985710360914275162674813760554"));
+        assert_eq!(a.bitwise(&b, |x, y| x | y), big("-121512828827855409466171785234"));
+        assert_eq!(a.bitwise(&b, |x, y| x ^ y), big("-1107223189742130572140985545788"));
+        assert_eq!(BigInt::pow10(30).bitwise(&BigInt::pow10(30).negate(), |x, y| x & y), BigInt::from_i128(1 << 30));
+        assert_eq!(BigInt::from_i128(2).pow(64).bitwise(&BigInt::from_i128(1), |x, y| x & y), BigInt::zero());
+        assert_eq!(BigInt::from_i128(2).pow(64).bitwise(&BigInt::from_i128(-1), |x, y| x ^ y), big("-18446744073709551617"));
+        assert_eq!(BigInt::from_i128(-1).bitwise(&BigInt::zero(), |x, y| x | y), BigInt::from_i128(-1));
+    }
+
+    #[test]
+    fn shifts_like_the_integers() {
+        let a = big("-123456789012345678901234567890");
+        assert_eq!(a.shift_right(37), big("-898266364037013256"));
+        assert_eq!(a.shift_left(45), big("-4343749601502796440598279644055484214804480"));
+        assert_eq!(BigInt::from_i128(-5).shift_right(1), BigInt::from_i128(-3));
+        assert_eq!(BigInt::from_i128(-5).shift_right(1000), BigInt::from_i128(-1));
+        assert_eq!(BigInt::from_i128(5).shift_right(1000), BigInt::zero());
+        assert_eq!(BigInt::from_i128(1).shift_left(100).to_string(), "1267650600228229401496703205376");
+    }
+
+    #[test]
+    fn compares_with_the_signs() {
+        assert!(big("-5") < big("3"));
+        assert!(big("-1000000000") < big("-999999999"));
+        assert!(big("1000000000") > big("999999999"));
+        assert_eq!(big("-12").to_i64(), Some(-12));
+        assert_eq!(BigInt::pow10(20).to_i64(), None);
+    }
+}
@@ -1,37 +1,31 @@
 /*
-The calculator takes an expression only made of Num(num) and Operator and it returns the result as an f64
+The calculator takes an expression only made of Num(num), List(list), Matrix(matrix) and Operator and it returns the result as a Value
  */
 
-use std::ops::Index;
-use crate::lang::calculator::Possible::{PossExpression, PossToken};
-use crate::lang::tokenizer::{Operator, Token};
+use std::collections::HashMap;
+use crate::lang::calculator::Possible::{PossCall, PossExpression, PossToken};
+use crate::lang::backend::{self, Backend};
+use crate::lang::bigint::BigInt;
+use crate::lang::formatter::format_tokens;
+use crate::lang::tokenizer::{Function, Operator, Token, MAX_EXACT};
 use crate::lang::tokenizer::Operator::Multiply;
-use crate::lang::tokenizer::Token::{Name, Number};
+use crate::lang::tokenizer::Token::Number;
+use crate::lang::value::Value;
 
-pub fn calculate(tokens: Vec<Token>) -> Result<f64, String> {
-    // Post process the tokens to add implicit multiplications, remove unnecessary parenthesis and determine minus signs
-    let tokens = post_process_operation(tokens);
+pub fn calculate(tokens: Vec<Token>) -> Result<Value, String> {
     // Compose a three with branch A and branch B possibles and the node an operation
     // A possible is either an Expression that needs to be resolved or a value
-    let three = three_composer(tokens.clone()?);
+    let three = parse_expression(tokens)?;
 
     // Resolve the three by propagating the solve methode
-    // If the expression given was correct, it shouldn't be a PossToken
-    let mut result = None;
-    if let Possible::PossExpression(expression) = three {
-        result = Some(expression.solve());
-    } else if let Possible::PossToken(tok) = three {
-        if let Number(num) = tok {
-            result = Some(Ok(num))
-        } else {
-            return Err(format!("Invalid token in calculation: {:?}, is not a number", tok))
-        }
-    }
-    if let Some(res) = result {
-        return Ok(res?)
-    } else {
-        return Err(format!("Invalid result of calculation: {:?}", tokens))
-    }
+    // If the expression given was correct, every leaf is a number or a list
+    three.solve()
+}
+
+pub fn parse_expression(tokens: Vec<Token>) -> Result<Possible, String> {
+    // Post process the tokens to add implicit multiplications, remove unnecessary parenthesis and determine minus signs
+    let tokens = post_process_operation(tokens)?;
+    three_composer(tokens)
 }
 /*
 
@@ -44,10 +38,23 @@ fn post_process_operation(tokens: Vec<Token>) -> Result<Vec<Token>, String> {
     let mut index = 0;
     while index < tokens.len() {
         if let Token::Operator(Operator::Minus) = &tokens[index] {
-            if index == 0 || !is_valid_preceding_token(&tokens[index - 1]) {
-                if let Some(Token::Number(number)) = tokens.get(index + 1) {
-                    tokens[index] = Token::Number(-number.clone());
-                    tokens.remove(index + 1);
+            // "-3!" is "-(3!)"
+            let factorial = tokens.get(index + 2) == Some(&Token::Operator(Operator::Factorial));
+            if (index == 0 || !is_valid_preceding_token(&tokens[index - 1])) && !factorial {
+                match tokens.get(index + 1) {
+                    Some(Token::Number(number)) => {
+                        tokens[index] = Token::Number(-number);
+                        tokens.remove(index + 1);
+                    }
+                    Some(Token::Decimal(number)) => {
+                        tokens[index] = Token::Decimal(number.negate());
+                        tokens.remove(index + 1);
+                    }
+                    Some(Token::Integer(number)) => {
+                        tokens[index] = Token::Integer(number.negate());
+                        tokens.remove(index + 1);
+                    }
+                    _ => {}
                 }
             }
         }
@@ -55,61 +62,76 @@ fn post_process_operation(tokens: Vec<Token>) -> Result<Vec<Token>, String> {
         index += 1;
     }
 
-    // Post process parenthesis
-    let mut open = Vec::new();
-    let mut index_adjustment = 0;  // Track the adjustment in indices due to removals and insertions
-
-    for i in 0..tokens.len() {
-        let adjusted_index = i - index_adjustment;
-        let token = tokens[adjusted_index].clone();
-
-        if let Token::ParenOpen = token {
-            open.push(adjusted_index);
-        } else if let Token::ParenClose = token {
-            let start = match open.last() {
-                None => {return Err("Mismatched parenthesis [1]".into())}
-                Some(num) => {*num}
-            };
-            let end = adjusted_index;
-            let mut extracted: Vec<Token> = tokens.drain(start..=end).collect();
+    // Post process parenthesis and brackets
+    let tokens = group(tokens)?;
 
-            // Adjust indices for the removals
-            index_adjustment += extracted.len() - 1;
-
-            // Remove the first and last elements (parentheses)
-            extracted.pop();
-            extracted.remove(0);
+    Ok(post_process_group(tokens))
+}
 
-            // Insert the Paren token at the original start index
-            tokens.insert(start, Token::Paren(extracted));
+// Applied on every level of parenthesis
+fn post_process_group(tokens: Vec<Token>) -> Vec<Token> {
+    let mut tokens: Vec<Token> = tokens.into_iter().map(|token| match token {
+        Token::Paren(inner) => Token::Paren(post_process_group(inner)),
+        Token::Bracket(inner) => Token::Bracket(post_process_group(inner)),
+        token => token,
+    }).collect();
 
-            open.pop();
+    // Factorials apply to the value before them: "n!" => "(n ! 0)", "n!!" => "((n ! 0) ! 0)"
+    let mut i = 1;
+    while i < tokens.len() {
+        if tokens[i] == Token::Operator(Operator::Factorial) && is_valid_preceding_token(&tokens[i - 1]) {
+            let operand = tokens.remove(i - 1);
+            tokens[i - 1] = Token::Paren(vec![operand, Token::Operator(Operator::Factorial), Number(0.0)]);
+        } else {
+            i += 1;
         }
     }
-    // Check parentheses not in pair, in this case, throw an error
-    for token in &mut *tokens {
-        if Token::ParenOpen == *token || Token::ParenClose == *token {
-            return Err("Mismatched parenthesis [2]".into())
+
+    // Minus signs in front of a value: "-2" => "-2", "-a" => "(-1 * a)"
+    let mut i = 0;
+    while i + 1 < tokens.len() {
+        if let Token::Operator(Operator::Minus) = &tokens[i] {
+            if i == 0 || !is_valid_preceding_token(&tokens[i - 1]) {
+                match tokens[i + 1].clone() {
+                    Number(number) => {
+                        tokens[i] = Number(-number);
+                        tokens.remove(i + 1);
+                    }
+                    Token::Decimal(number) => {
+                        tokens[i] = Token::Decimal(number.negate());
+                        tokens.remove(i + 1);
+                    }
+                    Token::Integer(number) => {
+                        tokens[i] = Token::Integer(number.negate());
+                        tokens.remove(i + 1);
+                    }
+                    next @ (Token::Name(_) | Token::Paren(_) | Token::List(_) | Token::Matrix(_) | Token::Bracket(_)) => {
+                        tokens[i] = Token::Paren(vec![Number(-1.0), Token::Operator(Multiply), next]);
+                        tokens.remove(i + 1);
+                    }
+                    // "-sin x" => "(-1 * sin x)"
+                    function @ Token::Function(_) if i + 2 < tokens.len() => {
+                        let argument = tokens.remove(i + 2);
+                        tokens[i] = Token::Paren(vec![Number(-1.0), Token::Operator(Multiply), function, argument]);
+                        tokens.remove(i + 1);
+                    }
+                    _ => {}
+                }
+            }
         }
+        i += 1;
     }
 
     // Add Operator::Multiply as needed
-    for i in 1..tokens.len() {
-        let add_operator_multiply = match (&tokens[i], &tokens[i - 1]) {
-            (Token::Name(_), Token::Number(_)) => true,
-            (Token::Name(_), Token::Name(_)) => true,
-            (Token::Number(_), Token::Number(_)) => true,
-            (Token::Paren(_), Token::Number(_)) | (Token::Paren(_), Token::Name(_)) => true,
-            (Token::Number(_), Token::Paren(_)) | (Token::Name(_), Token::Paren(_)) => true,
-            (Token::Paren(_), Token::Paren(_)) => true,
-            _ => false,
-        };
-
-        if add_operator_multiply {
+    // The range grows with every insertion, a for loop would stop before the last tokens
+    let mut i = 1;
+    while i < tokens.len() {
+        if implicit_multiplication(&tokens[i - 1], &tokens[i]) {
             tokens.insert(i, Token::Operator(Multiply));
         }
+        i += 1;
     }
-    //TODO not sure why that works
+
     //Unwrap nested parentheses recursively
     let mut i = 0;
     while i < tokens.len() {
@@ -125,81 +147,60 @@ fn post_process_operation(tokens: Vec<Token>) -> Result<Vec<Token>, String> {
         i += 1;
     }
 
-    // for i in 0..tokens.len() {
-    //     let current = tokens.get(i).unwrap();
-    //     if let Token::Paren(inner_tokens) = current {
-    //         tokens[i] = remove_paren(current.clone());
-    //     }
-    // }
-
-    Ok(tokens)
+    tokens
 }
 
-fn remove_paren(token: Token) -> Result<Token, String> {
-    if let Token::Paren(ref inner_tokens) = token {
-        if inner_tokens.len() == 1 {
-            if let Token::Paren(_) = inner_tokens.get(0).unwrap() {
-                return remove_paren(inner_tokens.get(0).unwrap().clone())
-            } else {
-                return Ok(inner_tokens.get(0).unwrap().clone())
-            }
-        } else {
-            return Ok( token.clone())
-        }
-    } else {
-        return Err("Unexpected Error [137]".to_string())
+// Two tokens next to each other are multiplied: "2a", "a b", "2(a + 1)", "(a)(b)", "2 sin(x)"
+// A bracket is never multiplied, "l[0]" is an index
+pub fn implicit_multiplication(previous: &Token, current: &Token) -> bool {
+    match (current, previous) {
+        (Token::Function(_), Token::Number(_) | Token::Decimal(_) | Token::Integer(_) | Token::Name(_) | Token::Paren(_) | Token::List(_) | Token::Matrix(_)) => true,
+        // Lists and matrices are only there once the variables are replaced, "2l" => "2 * [1, 2]"
+        (Token::List(_) | Token::Matrix(_), Token::Number(_) | Token::Decimal(_) | Token::Integer(_) | Token::Paren(_) | Token::List(_) | Token::Matrix(_)) => true,
+        (Token::Number(_) | Token::Decimal(_) | Token::Integer(_) | Token::Paren(_), Token::List(_) | Token::Matrix(_)) => true,
+        (Token::Name(_), Token::Number(_) | Token::Decimal(_) | Token::Integer(_)) => true,
+        (Token::Name(_), Token::Name(_)) => true,
+        (Token::Number(_) | Token::Decimal(_) | Token::Integer(_), Token::Number(_) | Token::Decimal(_) | Token::Integer(_)) => true,
+        (Token::Paren(_), Token::Number(_) | Token::Decimal(_) | Token::Integer(_)) | (Token::Paren(_), Token::Name(_)) => true,
+        (Token::Number(_) | Token::Decimal(_) | Token::Integer(_), Token::Paren(_)) | (Token::Name(_), Token::Paren(_)) => true,
+        (Token::Paren(_), Token::Paren(_)) => true,
+        _ => false,
     }
 }
+
 fn is_valid_preceding_token(token: &Token) -> bool {
-    match token {
-        Token::Name(_) | Token::Number(_) | Token::ParenClose => true,
-        _ => false,
-    }
+    matches!(token, Token::Name(_) | Token::Number(_) | Token::Decimal(_) | Token::Integer(_) | Token::Operator(Operator::Factorial) | Token::ParenClose | Token::Paren(_) | Token::List(_) | Token::Matrix(_) | Token::Bracket(_))
 }
 /*
 
 Three creation functions
 
  */
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Expression {
-    a: Box<Possible>,
-    o: Operator,
-    b: Box<Possible>,
+    pub(crate) a: Box<Possible>,
+    pub(crate) o: Operator,
+    pub(crate) b: Box<Possible>,
 }
 
 impl Expression {
     pub fn new(a: Possible, o:Operator, b: Possible) -> Expression {
         Expression {a: Box::from(a), o, b: Box::from(b)}
     }
-    pub fn solve(&self) -> Result<f64, String> {
-        let a:f64 = match &*self.a {
-            PossExpression(expr) => expr.solve(),
-            PossToken(tok) => {
-                Ok(match tok {
-                    Number(num) => *num,
-                    _ => return Err(format!("Invalid token in expression {:?}", tok))
-                })
-            }
-        }?;
-        let b:f64 = match &*self.b {
-            PossExpression(expr) => expr.solve(),
-            PossToken(tok) => {
-                Ok(match tok {
-                    Number(num) => *num,
-                    _ => return Err(format!("Invalid token in expression {:?}", tok))
-                })
-            }
-        }?;
+    pub fn solve(&self) -> Result<Value, String> {
+        let a = self.a.solve()?;
+        let b = self.b.solve()?;
 
-        let result: f64 = self.o.operate(a, b)?;
-        return Ok(result);
+        let result = a.operate(&self.o, b)?;
+        Ok(result)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Possible {
     PossExpression(Box<Expression>),
+    PossCall(Function, Vec<Possible>),
     PossToken(Token),
 }
 
@@ -210,11 +211,65 @@ impl Possible {
     pub fn token(tok: Token) -> Possible {
         PossToken(tok)
     }
+    pub fn solve(&self) -> Result<Value, String> {
+        match self {
+            PossExpression(expr) => expr.solve(),
+            PossCall(function, args) => {
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(arg.solve()?);
+                }
+                function.apply(values)
+            }
+            // Integers are exact with the default backend
+            PossToken(Number(num)) if backend::selected() == Backend::Double && num.fract() == 0.0 && num.abs() <= MAX_EXACT => {
+                Ok(Value::Integer(BigInt::from_i128(*num as i128)))
+            }
+            PossToken(Number(num)) => Ok(Value::Number(backend::selected().round(*num)?)),
+            PossToken(Token::Decimal(num)) => Ok(Value::Decimal(num.clone())),
+            PossToken(Token::Integer(num)) => Ok(Value::Integer(num.clone())),
+            PossToken(Token::List(list)) => Ok(Value::List(list.clone())),
+            PossToken(Token::Matrix(matrix)) => Ok(Value::Matrix(matrix.clone())),
+            PossToken(tok) => Err(format!("Invalid token in expression {:?}", tok)),
+        }
+    }
+    // Replace the names of the three with their value in memory
+    pub fn substitute(self, memory: &HashMap<String, Value>) -> Result<Possible, String> {
+        Ok(match self {
+            PossExpression(expr) => {
+                let Expression { a, o, b } = *expr;
+                Possible::expression(Expression::new(a.substitute(memory)?, o, b.substitute(memory)?))
+            }
+            PossCall(function, args) => {
+                let mut substituted = Vec::new();
+                for arg in args {
+                    substituted.push(arg.substitute(memory)?);
+                }
+                PossCall(function, substituted)
+            }
+            PossToken(Token::Name(name)) => match memory.get(&name) {
+                Some(value) => PossToken(value.clone().token()),
+                None => return Err(format!("Var [{}] doesn't exist", name)),
+            },
+            token => token,
+        })
+    }
 }
 
-fn three_composer(tokens: Vec<Token>) -> Possible {
+fn three_composer(tokens: Vec<Token>) -> Result<Possible, String> {
     if tokens.len() == 1 {
-        return Possible::token(tokens.get(0).unwrap().clone())
+        return match tokens.into_iter().next().unwrap() {
+            Token::Paren(toks) => three_composer(toks),
+            tok => Ok(Possible::token(tok)),
+        }
+    }
+    // A function applied to its arguments: "sin x", "sin(x + 1)", "sum(k, k, 1, 10)"
+    if let [Token::Function(function), argument] = &tokens[..] {
+        let mut args = Vec::new();
+        for arg in split_arguments(std::slice::from_ref(argument)) {
+            args.push(three_composer(arg)?);
+        }
+        return Ok(PossCall(function.clone(), args))
     }
     let mut version: (Vec<Token>, Operator, Vec<Token>) = (vec![], Operator::None, vec![]);
     for i in 0..tokens.len() {
@@ -223,7 +278,7 @@ fn three_composer(tokens: Vec<Token>) -> Possible {
             if let Token::Operator(o) = token {
                 if version.1.clone().over(o.clone()) {
                     let tokens_copy = tokens.clone();
-                    let (mut a, mut b) = tokens_copy.split_at(i);
+                    let (a, b) = tokens_copy.split_at(i);
                     let a = Vec::from(a);
                     let b: Vec<Token> = Vec::from(&b[1..]);
                     version = (a, o, b);
@@ -231,76 +286,30 @@ fn three_composer(tokens: Vec<Token>) -> Possible {
             }
         }
     }
-
+    if version.1 == Operator::None {
+        return Err(format!("Invalid expression: {}", format_tokens(&tokens)))
+    }
 
     //println!("version: {:?}", version);
-    let a =
-        if version.0.len() == 1 {
-            if let Some(Token::Paren(toks)) = version.0.get(0) {
-                if toks.len() == 1 {
-                    Possible::token(toks.get(0).unwrap().clone())
-                } else {
-                    three_composer(toks.clone())
-                }
-            } else {
-                Possible::token(version.0.get(0).unwrap().clone())
-            }
-        } else {
-            three_composer(version.0)
-        };
-    let b =
-        if version.2.len() == 1 {
-            if let Some(Token::Paren(toks)) = version.2.get(0) {
-                three_composer(toks.clone())
-            } else {
-                Possible::token(version.2.get(0).unwrap().clone())
-            }
-        } else {
-            three_composer(version.2)
-        };
-    Possible::expression(Expression::new(a, version.1, b))
+    let a = three_composer(version.0)?;
+    let b = three_composer(version.2)?;
+    Ok(Possible::expression(Expression::new(a, version.1, b)))
 }
 
-pub fn post_process_paren(tokens: Vec<Token>) -> Result<Vec<Token>, String> {
-    let mut tokens = tokens;
-
-    // Post process parenthesis
-    let mut open = Vec::new();
-    let mut index_adjustment = 0;  // Track the adjustment in indices due to removals and insertions
-
-    for i in 0..tokens.len() {
-        let adjusted_index = i - index_adjustment;
-        let token = tokens[adjusted_index].clone();
-
-        if let Token::ParenOpen = token {
-            open.push(adjusted_index);
-        } else if let Token::ParenClose = token {
-            let start = match open.last() {
-                None => {return Err("Mismatched parenthesis [3]".into())}
-                Some(some) => {*some}
-            };
-            let end = adjusted_index;
-            let mut extracted: Vec<Token> = tokens.drain(start..=end).collect();
-
-            // Adjust indices for the removals
-            index_adjustment += extracted.len() - 1;
-
-            // Remove the first and last elements (parentheses)
-            extracted.pop();
-            extracted.remove(0);
-
-            // Insert the Paren token at the original start index
-            tokens.insert(start, Token::Paren(extracted));
-
-            open.pop();
-        }
-    }
-    // Check parentheses not in pair, in this case, throw an error
-    for token in &mut *tokens {
-        if Token::ParenOpen == *token || Token::ParenClose == *token {
-            panic!("Mismatched parentheses [2]")
-        }
+// Arguments of a function call, "(a, b + 1)" => [a], [b + 1]
+pub fn split_arguments(tokens: &[Token]) -> Vec<Vec<Token>> {
+    let tokens = match tokens {
+        [Token::Paren(inner)] => inner,
+        tokens => tokens,
+    };
+    if tokens.is_empty() {
+        return vec![]
     }
+    tokens.split(|token| *token == Token::Comma).map(|arg| arg.to_vec()).collect()
+}
+
+pub fn post_process_paren(tokens: Vec<Token>) -> Result<Vec<Token>, String> {
+    let mut tokens = group(tokens)?;
 
     //TODO not sure why that works
     //Unwrap nested parentheses recursively
@@ -319,4 +328,38 @@ pub fn post_process_paren(tokens: Vec<Token>) -> Result<Vec<Token>, String> {
     }
 
     Ok(tokens)
-}
\ No newline at end of file
+}
+
+// Group the tokens between "(" and ")" into a Paren and the ones between "[" and "]" into a Bracket
+fn group(tokens: Vec<Token>) -> Result<Vec<Token>, String> {
+    // Opening token of every unclosed group with the tokens before it
+    let mut open: Vec<(Token, Vec<Token>)> = Vec::new();
+    let mut current = Vec::new();
+    for token in tokens {
+        match token {
+            Token::ParenOpen | Token::BracketOpen => open.push((token, std::mem::take(&mut current))),
+            Token::ParenClose | Token::BracketClose => {
+                let (opening, before) = open.pop().ok_or_else(|| mismatched(&token))?;
+                let grouped = match (opening, &token) {
+                    (Token::ParenOpen, Token::ParenClose) => Token::Paren(current),
+                    (Token::BracketOpen, Token::BracketClose) => Token::Bracket(current),
+                    _ => return Err(mismatched(&token)),
+                };
+                current = before;
+                current.push(grouped);
+            }
+            token => current.push(token),
+        }
+    }
+    match open.pop() {
+        Some((opening, _)) => Err(mismatched(&opening)),
+        None => Ok(current),
+    }
+}
+
+fn mismatched(token: &Token) -> String {
+    match token {
+        Token::BracketOpen | Token::BracketClose => "Mismatched brackets".into(),
+        _ => "Mismatched parenthesis".into(),
+    }
+}
@@ -0,0 +1,253 @@
+/*
+The checker goes through a script without running it and reports the instructions that are likely to fail:
+variables read before any assignment, constant conditions, loops never updating their condition
+and divisions by a literal zero
+ */
+
+use std::collections::HashSet;
+use crate::lang::calculator::{calculate, post_process_paren, split_arguments};
+use crate::lang::line_type::{construct_line_type, Condition, LineType};
+use crate::lang::tokenizer::{tokenize2, Operation, Operator, Token};
+
+pub fn check_script(script: &str) -> Vec<(usize, String)> {
+    let mut warnings = Vec::new();
+    let mut assigned = HashSet::new();
+    for (i, line) in script.lines().enumerate() {
+        let number = i + 1;
+        match tokenize2(line).and_then(construct_line_type) {
+            Ok(line_type) => check_line(number, line_type, &mut assigned, &mut warnings),
+            Err(err) => warnings.push((number, err)),
+        }
+    }
+    warnings
+}
+
+fn check_line(number: usize, line_type: LineType, assigned: &mut HashSet<String>, warnings: &mut Vec<(usize, String)>) {
+    match line_type {
+        LineType::Nothing => {}
+        LineType::Out(names) => {
+            for name in names {
+                check_read(number, &name, assigned, warnings);
+            }
+        }
+        LineType::VarOperate(var, op, ex) => {
+            check_expression(number, &ex, assigned, warnings);
+            if matches!(op, Operation::DivideVar | Operation::ModuloVar) && is_zero(&ex) {
+                warnings.push((number, format!("Division of [{}] by zero", var)));
+            }
+            if op != Operation::Assign {
+                check_read(number, &var, assigned, warnings);
+            }
+            assigned.insert(var);
+        }
+        LineType::If(condition, actions) => {
+            check_condition(number, "if", &condition, assigned, warnings);
+            check_body(number, actions, assigned, warnings);
+        }
+        LineType::While(condition, actions) => {
+            check_condition(number, "while", &condition, assigned, warnings);
+            let mut modified = HashSet::new();
+            for action in &actions {
+                // "solve x: ..." stores its root in x
+                if let Ok(LineType::VarOperate(var, _, _) | LineType::Solve(var, _, _)) = construct_line_type(action.clone()) {
+                    modified.insert(var);
+                }
+            }
+            let mut names = Vec::new();
+            for operand in condition.operands() {
+                read_names(operand, &mut names);
+            }
+            if !names.is_empty() && !names.iter().any(|name| modified.contains(name)) {
+                warnings.push((number, format!("Loop condition variables [{}] are never modified in the loop", names.join(", "))));
+            }
+            check_body(number, actions, assigned, warnings);
+        }
+        LineType::Solve(var, a, b) => {
+            let mut names = Vec::new();
+            read_names(&a, &mut names);
+            read_names(&b, &mut names);
+            for name in names.iter().filter(|name| **name != var) {
+                check_read(number, name, assigned, warnings);
+            }
+            if !names.contains(&var) {
+                warnings.push((number, format!("Equation doesn't depend on [{}]", var)));
+            }
+            assigned.insert(var);
+        }
+    }
+}
+
+fn check_body(number: usize, actions: Vec<Vec<Token>>, assigned: &mut HashSet<String>, warnings: &mut Vec<(usize, String)>) {
+    for action in actions {
+        match construct_line_type(action) {
+            Ok(line_type) => check_line(number, line_type, assigned, warnings),
+            Err(err) => warnings.push((number, err)),
+        }
+    }
+}
+
+fn check_condition(number: usize, keyword: &str, condition: &Condition, assigned: &HashSet<String>, warnings: &mut Vec<(usize, String)>) {
+    let mut names = Vec::new();
+    for operand in condition.operands() {
+        check_expression(number, operand, assigned, warnings);
+        read_names(operand, &mut names);
+    }
+    if names.is_empty() && !condition.operands().any(|operand| has_random(operand)) {
+        if let Ok(value) = constant_condition(condition) {
+            warnings.push((number, format!("Condition of {} is always {}", keyword, value)));
+        }
+    }
+}
+
+// Value of a condition without variables
+fn constant_condition(condition: &Condition) -> Result<bool, String> {
+    let mut a = calculate(condition.first.clone())?.number()?;
+    let mut value = true;
+    for (c, operand) in &condition.rest {
+        let b = calculate(operand.clone())?.number()?;
+        value = value && c.compare(a, b);
+        a = b;
+    }
+    Ok(value)
+}
+
+fn check_expression(number: usize, tokens: &[Token], assigned: &HashSet<String>, warnings: &mut Vec<(usize, String)>) {
+    let tokens = match post_process_paren(tokens.to_vec()) {
+        Ok(tokens) => tokens,
+        Err(err) => return warnings.push((number, err)),
+    };
+    let mut names = Vec::new();
+    read_names(&tokens, &mut names);
+    for name in names {
+        check_read(number, &name, assigned, warnings);
+    }
+    if has_literal_division_by_zero(&tokens) {
+        warnings.push((number, "Division by zero".to_string()));
+    }
+}
+
+fn check_read(number: usize, name: &str, assigned: &HashSet<String>, warnings: &mut Vec<(usize, String)>) {
+    if !assigned.contains(name) {
+        warnings.push((number, format!("Var [{}] is read before any assignment", name)));
+    }
+}
+
+fn has_literal_division_by_zero(tokens: &[Token]) -> bool {
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Operator(Operator::Divide | Operator::Modulo | Operator::IntDivide) => {
+                if let Some(Token::Number(num)) = tokens.get(i + 1) {
+                    if *num == 0.0 {
+                        return true
+                    }
+                }
+            }
+            Token::Paren(inner) if has_literal_division_by_zero(inner) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn is_zero(tokens: &[Token]) -> bool {
+    matches!(post_process_paren(tokens.to_vec()).as_deref(), Ok([Token::Number(num)]) if *num == 0.0)
+}
+
+// Names read by an expression, without duplicates and in order of appearance
+fn read_names(tokens: &[Token], names: &mut Vec<String>) {
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Name(name) if !names.contains(name) => names.push(name.clone()),
+            Token::Paren(inner) => {
+                let mut inner_names = Vec::new();
+                read_names(inner, &mut inner_names);
+                // The variable of sum(k^2, k, 1, n) is assigned by the function itself
+                let bound = bound_variable(&tokens[..i], token);
+                for name in inner_names {
+                    if Some(&name) != bound.as_ref() && !names.contains(&name) {
+                        names.push(name)
+                    }
+                }
+            }
+            Token::Bracket(inner) => read_names(inner, names),
+            _ => {}
+        }
+    }
+}
+
+fn has_random(tokens: &[Token]) -> bool {
+    tokens.iter().any(|token| match token {
+        Token::Function(function) => function.is_random(),
+        Token::Paren(inner) | Token::Bracket(inner) => has_random(inner),
+        _ => false,
+    })
+}
+
+fn bound_variable(before: &[Token], args: &Token) -> Option<String> {
+    match before.last() {
+        Some(Token::Function(function)) if function.binds_variable() => {}
+        _ => return None,
+    }
+    match &split_arguments(std::slice::from_ref(args))[..] {
+        [_, var, ..] => match &var[..] {
+            [Token::Name(var)] => Some(var.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warnings(script: &str) -> Vec<(usize, String)> {
+        check_script(script)
+    }
+
+    #[test]
+    fn accepts_a_correct_script() {
+        assert!(warnings("i = 0\nt = 0\nwl i < 10: t += i: i += 1\nt\n").is_empty());
+    }
+
+    #[test]
+    fn reports_the_variables_read_before_any_assignment() {
+        assert_eq!(warnings("a = b + 1\na"), vec![(1, "Var [b] is read before any assignment".to_string())]);
+        assert_eq!(warnings("a += 1"), vec![(1, "Var [a] is read before any assignment".to_string())]);
+        assert_eq!(warnings("c"), vec![(1, "Var [c] is read before any assignment".to_string())]);
+        assert_eq!(warnings("if x > 0: y = 1"), vec![(1, "Var [x] is read before any assignment".to_string())]);
+    }
+
+    #[test]
+    fn reports_the_constant_conditions() {
+        assert_eq!(warnings("if 1 < 2: a = 1"), vec![(1, "Condition of if is always true".to_string())]);
+        assert_eq!(warnings("a = 0\nwl 2 == 3: a += 1"), vec![(2, "Condition of while is always false".to_string())]);
+    }
+
+    #[test]
+    fn reports_the_loops_never_modifying_their_condition() {
+        assert_eq!(warnings("i = 0\nt = 0\nwl i < 10: t += 1"),
+            vec![(3, "Loop condition variables [i] are never modified in the loop".to_string())]);
+        assert!(warnings("i = 0\nwl i < 10: i *= 2").is_empty());
+    }
+
+    #[test]
+    fn checks_the_equations() {
+        assert_eq!(warnings("x = 1\nsolve x: 2 == 3"), vec![(2, "Equation doesn't depend on [x]".to_string())]);
+        assert_eq!(warnings("solve x: x^2 == a"), vec![(1, "Var [a] is read before any assignment".to_string())]);
+        // The root is stored in x, which updates the condition
+        assert!(warnings("x = 1\nn = 0\nwl x < 5: n += 1: solve x: x^2 == n + 3").is_empty());
+    }
+
+    #[test]
+    fn reports_the_divisions_by_zero() {
+        assert_eq!(warnings("a = 1 / 0"), vec![(1, "Division by zero".to_string())]);
+        assert_eq!(warnings("a = 1\na /= 0"), vec![(2, "Division of [a] by zero".to_string())]);
+        assert!(warnings("a = 0\nb = 1 / a").is_empty());
+    }
+
+    #[test]
+    fn reports_the_lines_that_dont_parse() {
+        assert_eq!(warnings("a = (1 + 2").len(), 1);
+    }
+}
@@ -0,0 +1,490 @@
+/*
+Decimal numbers of arbitrary precision for $backend decimal, mantissa * 10^exponent with a BigInt mantissa.
+Every result is rounded half away from zero to the digits of the backend (50 by default):
++ - * / are exact before the rounding, sqrt, ln, exp, ^ and the constants PI and e are computed
+with guard digits so all the digits given are right. The other functions are computed in f64
+ */
+
+use std::cmp::Ordering;
+use std::fmt;
+use crate::lang::backend;
+use crate::lang::bigint::BigInt;
+use crate::lang::numeric::Numeric;
+use crate::lang::tokenizer;
+
+// Extra digits of the intermediate results of sqrt, ln, exp and ^
+const GUARD: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct Decimal {
+    mantissa: BigInt,
+    exponent: i64,
+}
+
+impl Decimal {
+    pub fn from_integer(n: i128) -> Decimal {
+        Decimal { mantissa: BigInt::from_i128(n), exponent: 0 }
+    }
+
+    pub fn from_bigint(n: BigInt) -> Decimal {
+        Decimal { mantissa: n, exponent: 0 }.normalized()
+    }
+
+    // Exact value of a number of the script, "0.1" or "12"
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let mantissa = BigInt::parse(&format!("{}{}", integer, fraction))?;
+        Some(Decimal { mantissa, exponent: -(fraction.len() as i64) }.normalized())
+    }
+
+    // Without the zeros at the end of the mantissa, so equal numbers have the same fields
+    fn normalized(self) -> Decimal {
+        if self.mantissa.is_zero() {
+            return Decimal { mantissa: self.mantissa, exponent: 0 }
+        }
+        let text = self.mantissa.to_string();
+        let zeros = text.len() - text.trim_end_matches('0').len();
+        if zeros == 0 {
+            return self
+        }
+        let (mantissa, _) = self.mantissa.divide_pow10(zeros);
+        Decimal { mantissa, exponent: self.exponent + zeros as i64 }
+    }
+
+    // Rounded half away from zero to a number of significant digits
+    fn round(mantissa: BigInt, exponent: i64, digits: usize) -> Decimal {
+        let length = mantissa.digits();
+        if length <= digits {
+            return Decimal { mantissa, exponent }.normalized()
+        }
+        let dropped = length - digits;
+        let (mut kept, rest) = mantissa.divide_pow10(dropped);
+        let (first, _) = rest.abs().divide_pow10(dropped - 1);
+        if first >= BigInt::from_i128(5) {
+            kept = kept.add(&BigInt::from_i128(if mantissa.is_negative() { -1 } else { 1 }));
+        }
+        Decimal { mantissa: kept, exponent: exponent + dropped as i64 }.normalized()
+    }
+
+    fn rounded(&self, digits: usize) -> Decimal {
+        Decimal::round(self.mantissa.clone(), self.exponent, digits)
+    }
+
+    // Position of the first digit: 2 for 123.4, -1 for 0.05
+    fn magnitude(&self) -> i64 {
+        self.exponent + self.mantissa.digits() as i64 - 1
+    }
+
+    fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    pub fn negate(&self) -> Decimal {
+        Decimal { mantissa: self.mantissa.negate(), exponent: self.exponent }
+    }
+
+    fn sum(&self, other: &Decimal, digits: usize) -> Decimal {
+        if other.mantissa.is_zero() {
+            return self.rounded(digits)
+        }
+        if self.mantissa.is_zero() {
+            return other.rounded(digits)
+        }
+        let (high, low) = if self.magnitude() >= other.magnitude() { (self, other) } else { (other, self) };
+        // Below a tenth of the last digit kept, the smaller number can't change the rounding of a rounded number
+        if high.mantissa.digits() <= digits && high.magnitude() - low.magnitude() > digits as i64 + 1 {
+            return high.rounded(digits)
+        }
+        let exponent = high.exponent.min(low.exponent);
+        let shifted = |x: &Decimal| x.mantissa.multiply(&BigInt::pow10((x.exponent - exponent) as usize));
+        Decimal::round(shifted(high).add(&shifted(low)), exponent, digits)
+    }
+
+    fn product(&self, other: &Decimal, digits: usize) -> Decimal {
+        Decimal::round(self.mantissa.multiply(&other.mantissa), self.exponent + other.exponent, digits)
+    }
+
+    // The quotient has 2 digits more than needed, the truncated remainder can't change its rounding
+    fn quotient(&self, other: &Decimal, digits: usize) -> Result<Decimal, String> {
+        if other.mantissa.is_zero() {
+            return Err(format!("Division by zero: {} / 0", self))
+        }
+        let shift = (digits + 2 + other.mantissa.digits()).saturating_sub(self.mantissa.digits());
+        let (quotient, _) = self.mantissa.multiply(&BigInt::pow10(shift)).divide(&other.mantissa)?;
+        Ok(Decimal::round(quotient, self.exponent - other.exponent - shift as i64, digits))
+    }
+
+    fn square_root(&self, digits: usize) -> Decimal {
+        if self.mantissa.is_zero() {
+            return self.clone()
+        }
+        // The integer root of mantissa * 10^shift has digits + 2 digits, with an even exponent left
+        let mut shift = (2 * (digits + 2)).saturating_sub(self.mantissa.digits());
+        if (self.exponent - shift as i64) % 2 != 0 {
+            shift += 1;
+        }
+        let root = self.mantissa.multiply(&BigInt::pow10(shift)).sqrt();
+        Decimal::round(root, (self.exponent - shift as i64) / 2, digits)
+    }
+
+    // exp(x) = exp(x / 2^n)^(2^n) with x / 2^n small enough for the series to converge fast
+    fn exponential(&self, digits: usize) -> Result<Decimal, String> {
+        let x = self.to_f64();
+        if x > 1e15 {
+            return Err(format!("Overflow of exp({})", self))
+        }
+        if x < -1e15 {
+            return Ok(Decimal::from_integer(0))
+        }
+        let halvings = if x.abs() > 0.01 { (x.abs() / 0.01).log2().ceil() as u32 } else { 0 };
+        // Every squaring doubles the relative error
+        let work = digits + GUARD + halvings as usize / 3;
+        let reduced = self.quotient(&Decimal { mantissa: BigInt::from_i128(2).pow(halvings), exponent: 0 }, work)?;
+        let one = Decimal::from_integer(1);
+        let mut sum = one.clone();
+        let mut term = one;
+        let mut k = 1;
+        loop {
+            term = term.product(&reduced, work).quotient(&Decimal::from_integer(k), work)?;
+            if term.mantissa.is_zero() || term.magnitude() < sum.magnitude() - work as i64 {
+                break
+            }
+            sum = sum.sum(&term, work);
+            k += 1;
+        }
+        for _ in 0..halvings {
+            sum = sum.product(&sum, work);
+        }
+        Ok(sum.rounded(digits))
+    }
+
+    // ln(m * 10^k) = ln(m) + k * ln(10) with m in [1, 10)
+    fn logarithm(&self, digits: usize) -> Result<Decimal, String> {
+        let k = self.magnitude();
+        let mut work = digits + GUARD + k.unsigned_abs().to_string().len();
+        // Close to 1, the first digits of x - 1 cancel
+        let distance = self.sum(&Decimal::from_integer(-1), digits);
+        if !distance.mantissa.is_zero() && distance.magnitude() < 0 {
+            work += distance.magnitude().unsigned_abs() as usize;
+        }
+        let m = Decimal { mantissa: self.mantissa.clone(), exponent: self.exponent - k };
+        let mut result = m.reduced_logarithm(work)?;
+        if k != 0 {
+            let ten = Decimal::from_integer(10).reduced_logarithm(work)?;
+            result = result.sum(&ten.product(&Decimal::from_integer(k as i128), work), work);
+        }
+        Ok(result.rounded(digits))
+    }
+
+    // ln(x) = 2^n * ln(x^(1 / 2^n)) and ln(y) = 2 * atanh((y - 1) / (y + 1)), for x not far from 1
+    fn reduced_logarithm(&self, digits: usize) -> Result<Decimal, String> {
+        const ROOTS: u32 = 12;
+        let work = digits + GUARD;
+        let mut y = self.clone();
+        for _ in 0..ROOTS {
+            y = y.square_root(work);
+        }
+        let one = Decimal::from_integer(1);
+        let z = y.sum(&one.negate(), work).quotient(&y.sum(&one, work), work)?;
+        let z2 = z.product(&z, work);
+        let mut sum = z.clone();
+        let mut power = z;
+        let mut k = 3;
+        loop {
+            power = power.product(&z2, work);
+            let term = power.quotient(&Decimal::from_integer(k), work)?;
+            if term.mantissa.is_zero() || term.magnitude() < sum.magnitude() - work as i64 {
+                break
+            }
+            sum = sum.sum(&term, work);
+            k += 2;
+        }
+        Ok(sum.product(&Decimal::from_integer(2i128.pow(ROOTS + 1)), work))
+    }
+
+    // Machin's formula: PI = 16 atan(1/5) - 4 atan(1/239), none of the divisors is zero
+    pub fn pi(digits: usize) -> Decimal {
+        let work = digits + GUARD;
+        let scale = BigInt::pow10(work);
+        let atan_inverse = |n: i128| {
+            let n2 = BigInt::from_i128(n * n);
+            let mut power = scale.divide(&BigInt::from_i128(n)).unwrap().0;
+            let mut sum = power.clone();
+            let mut k = 1;
+            loop {
+                power = power.divide(&n2).unwrap().0;
+                let term = power.divide(&BigInt::from_i128(2 * k + 1)).unwrap().0;
+                if term.is_zero() {
+                    return sum
+                }
+                sum = if k % 2 == 1 { sum.subtract(&term) } else { sum.add(&term) };
+                k += 1;
+            }
+        };
+        let pi = atan_inverse(5).multiply(&BigInt::from_i128(16)).subtract(&atan_inverse(239).multiply(&BigInt::from_i128(4)));
+        Decimal::round(pi, -(work as i64), digits)
+    }
+
+    pub fn e(digits: usize) -> Decimal {
+        Decimal::from_integer(1).exponential(digits).unwrap()
+    }
+
+    fn is_integer(&self) -> bool {
+        self.exponent >= 0 || self.mantissa.is_zero()
+    }
+
+    // Exact before being rounded like the other operations
+    pub fn factorial(&self) -> Result<Decimal, String> {
+        if !self.is_integer() {
+            return Err(format!("Factorial of {}, it is only defined for the natural numbers", self))
+        }
+        let n = tokenizer::factorial_argument(self.to_f64())?;
+        Ok(Decimal::from_bigint(BigInt::factorial(n)).rounded(digits()))
+    }
+
+    // x^n by squaring, the error grows with the number of products
+    fn integer_power(&self, n: &BigInt, digits: usize) -> Result<Decimal, String> {
+        let exponent = match n.abs().to_i64() {
+            Some(exponent) if exponent <= u32::MAX as i64 => exponent as u32,
+            _ => return Err(format!("Exponent {} is too large", n)),
+        };
+        let work = digits + GUARD;
+        let mut result = Decimal::from_integer(1);
+        let mut base = self.clone();
+        let mut e = exponent;
+        while e > 0 {
+            if e & 1 == 1 {
+                result = result.product(&base, work);
+            }
+            e >>= 1;
+            if e > 0 {
+                base = base.product(&base, work);
+            }
+        }
+        if n.is_negative() {
+            if result.mantissa.is_zero() {
+                return Err(format!("Division by zero: {}^{}", self, n))
+            }
+            result = Decimal::from_integer(1).quotient(&result, work)?;
+        }
+        Ok(result.rounded(digits))
+    }
+}
+
+fn digits() -> usize {
+    backend::selected().decimal_digits()
+}
+
+impl Numeric for Decimal {
+    // The shortest text giving back the float: 0.1 is exactly 0.1
+    fn from_f64(x: f64) -> Result<Decimal, String> {
+        if !x.is_finite() {
+            return Err(format!("{} has no decimal value", x))
+        }
+        let text = format!("{:e}", x);
+        let (digits, exponent) = text.split_once('e').unwrap();
+        let negative = digits.starts_with('-');
+        let digits = digits.trim_start_matches('-');
+        let decimal = Decimal::parse(digits).unwrap();
+        let decimal = Decimal { mantissa: decimal.mantissa, exponent: decimal.exponent + exponent.parse::<i64>().unwrap() };
+        Ok(if negative { decimal.negate() } else { decimal })
+    }
+
+    fn to_f64(&self) -> f64 {
+        format!("{}e{}", self.mantissa, self.exponent).parse::<f64>().unwrap()
+    }
+
+    fn add(self, other: Decimal) -> Result<Decimal, String> {
+        Ok(self.sum(&other, digits()))
+    }
+
+    fn subtract(self, other: Decimal) -> Result<Decimal, String> {
+        Ok(self.sum(&other.negate(), digits()))
+    }
+
+    fn multiply(self, other: Decimal) -> Result<Decimal, String> {
+        Ok(self.product(&other, digits()))
+    }
+
+    fn divide(self, other: Decimal) -> Result<Decimal, String> {
+        self.quotient(&other, digits())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    fn floor(self) -> Result<Decimal, String> {
+        if self.is_integer() {
+            return Ok(self)
+        }
+        let (mut quotient, rest) = self.mantissa.divide_pow10(self.exponent.unsigned_abs() as usize);
+        if rest.is_negative() {
+            quotient = quotient.subtract(&BigInt::from_i128(1));
+        }
+        Ok(Decimal { mantissa: quotient, exponent: 0 }.normalized())
+    }
+
+    fn power(self, other: Decimal) -> Result<Decimal, String> {
+        if other.is_integer() {
+            let n = other.mantissa.multiply(&BigInt::pow10(other.exponent as usize));
+            return self.integer_power(&n, digits())
+        }
+        if self.mantissa.is_zero() && !other.is_negative() {
+            return Ok(self)
+        }
+        if self.is_negative() || self.mantissa.is_zero() {
+            return Err(format!("{}^{} is not a real number", self, other))
+        }
+        // x^y = exp(y ln(x)), the error of the logarithm grows with y ln(x)
+        let size = (other.to_f64() * self.to_f64().ln()).abs();
+        let work = digits() + GUARD + size.log10().max(0.0) as usize;
+        other.product(&self.logarithm(work)?, work).exponential(digits())
+    }
+
+    fn log(self, base: Decimal) -> Result<Decimal, String> {
+        if self.is_negative() || self.mantissa.is_zero() || base.is_negative() || base.mantissa.is_zero() {
+            return Err(format!("Logarithm of a non positive number: {} // {}", self, base))
+        }
+        let work = digits() + GUARD;
+        let base_logarithm = base.logarithm(work)?;
+        if base_logarithm.mantissa.is_zero() {
+            return Err(format!("Logarithm in base 1: {} // {}", self, base))
+        }
+        Ok(self.logarithm(work)?.quotient(&base_logarithm, work)?.rounded(digits()))
+    }
+
+    fn sqrt(self) -> Result<Decimal, String> {
+        Ok(self.square_root(digits()))
+    }
+
+    fn ln(self) -> Result<Decimal, String> {
+        self.logarithm(digits())
+    }
+
+    fn exp(self) -> Result<Decimal, String> {
+        self.exponential(digits())
+    }
+
+    fn abs(self) -> Result<Decimal, String> {
+        Ok(Decimal { mantissa: self.mantissa.abs(), exponent: self.exponent })
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        let exponent = self.exponent.min(other.exponent);
+        let shifted = |x: &Decimal| x.mantissa.multiply(&BigInt::pow10((x.exponent - exponent) as usize));
+        Some(shifted(self).cmp(&shifted(other)))
+    }
+}
+
+// All the digits, without an exponent unless the number is very large or very small
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let digits = self.mantissa.abs().to_string();
+        let magnitude = self.magnitude();
+        if self.mantissa.is_zero() {
+            write!(f, "0")
+        } else if !(-20..100).contains(&magnitude) {
+            let (first, rest) = digits.split_at(1);
+            let point = if rest.is_empty() { "" } else { "." };
+            write!(f, "{}{}{}{}e{}", sign, first, point, rest, magnitude)
+        } else if self.exponent >= 0 {
+            write!(f, "{}{}{}", sign, digits, "0".repeat(self.exponent as usize))
+        } else if magnitude >= 0 {
+            let (integer, fraction) = digits.split_at(magnitude as usize + 1);
+            write!(f, "{}{}.{}", sign, integer, fraction)
+        } else {
+            write!(f, "{}0.{}{}", sign, "0".repeat((-magnitude - 1) as usize), digits)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Published digits, rounded to 100 significant digits
+    const PI_100: &str = "3.141592653589793238462643383279502884197169399375105820974944592307816406286208998628034825342117068";
+    const E_100: &str = "2.718281828459045235360287471352662497757247093699959574966967627724076630353547594571382178525166427";
+
+    fn decimal(text: &str) -> Decimal {
+        match text.strip_prefix('-') {
+            Some(text) => Decimal::parse(text).unwrap().negate(),
+            None => Decimal::parse(text).unwrap(),
+        }
+    }
+
+    #[test]
+    fn gives_the_digits_of_pi_and_e() {
+        assert_eq!(Decimal::pi(100).to_string(), PI_100);
+        assert_eq!(Decimal::e(100).to_string(), E_100);
+        assert_eq!(Decimal::pi(10).to_string(), "3.141592654");
+        assert_eq!(Decimal::e(1).to_string(), "3");
+    }
+
+    #[test]
+    fn computes_the_logarithms_and_exponentials() {
+        assert_eq!(decimal("2").ln().unwrap().to_string(), "0.69314718055994530941723212145817656807550013436026");
+        assert_eq!(decimal("10").ln().unwrap().to_string(), "2.3025850929940456840179914546843642076011014886288");
+        assert_eq!(decimal("-3").exp().unwrap().to_string(), "0.049787068367863942979342415650061776631699592188423");
+        assert_eq!(decimal("1.0000001").ln().unwrap().to_string(), "0.00000009999999500000033333330833333533333316666668095238");
+        assert_eq!(decimal("100").log(decimal("3")).unwrap().to_string(), "4.1918065485787692085931350440428025012150360135959");
+        assert_eq!(decimal("2").sqrt().unwrap().to_string(), "1.4142135623730950488016887242096980785696718753769");
+    }
+
+    // Difference at most 10^-48 times the scale
+    fn close(a: &Decimal, b: &Decimal, scale: &Decimal) -> bool {
+        let error = a.sum(&b.negate(), 60).abs().unwrap();
+        error <= scale.clone().abs().unwrap().product(&Decimal { mantissa: BigInt::from_i128(1), exponent: -48 }, 60)
+    }
+
+    #[test]
+    fn gives_back_the_number_of_a_logarithm() {
+        for text in ["2", "0.001", "12345.678", "1.0000001", "-0.5", "-40"] {
+            let x = decimal(text);
+            // The rounding of exp(x) to 50 digits moves its logarithm by about 10^-50
+            let back = x.clone().exp().unwrap().ln().unwrap();
+            assert!(close(&back, &x, &Decimal::from_integer(1)), "ln(exp({})) = {}", text, back);
+            if !x.is_negative() {
+                let back = x.clone().ln().unwrap().exp().unwrap();
+                assert!(close(&back, &x, &x), "exp(ln({})) = {}", text, back);
+            }
+        }
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero() {
+        assert_eq!(decimal("2").divide(decimal("3")).unwrap().to_string(), format!("0.{}7", "6".repeat(49)));
+        assert_eq!(decimal("-2").divide(decimal("3")).unwrap().to_string(), format!("-0.{}7", "6".repeat(49)));
+        assert_eq!(decimal("1").quotient(&decimal("8"), 2).unwrap().to_string(), "0.13");
+        assert_eq!(decimal("-1").quotient(&decimal("8"), 2).unwrap().to_string(), "-0.13");
+        assert_eq!(decimal("0.1").add(decimal("0.2")).unwrap().to_string(), "0.3");
+    }
+
+    #[test]
+    fn computes_with_negative_numbers() {
+        assert_eq!(decimal("-7.5").floor().unwrap().to_string(), "-8");
+        assert_eq!(decimal("-7").floor().unwrap().to_string(), "-7");
+        assert_eq!(decimal("-2").power(decimal("3")).unwrap().to_string(), "-8");
+        assert_eq!(decimal("-2").power(decimal("-2")).unwrap().to_string(), "0.25");
+        assert!(decimal("-2").power(decimal("0.5")).is_err());
+        assert!(decimal("-1.5").multiply(decimal("-2")).unwrap() == decimal("3"));
+    }
+
+    #[test]
+    fn rejects_the_divisions_by_zero() {
+        assert!(decimal("1").divide(decimal("0")).is_err());
+        assert!(decimal("0").power(decimal("-1")).is_err());
+        assert!(decimal("5").log(decimal("1")).is_err());
+        assert!(decimal("5").log(decimal("0")).is_err());
+    }
+}
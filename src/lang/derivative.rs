@@ -0,0 +1,179 @@
+/*
+Symbolic differentiation of an expression three with respect to one variable,
+the result is a new three that should be simplified before being printed
+ */
+
+use crate::lang::calculator::{Expression, Possible};
+use crate::lang::calculator::Possible::{PossCall, PossExpression, PossToken};
+use crate::lang::tokenizer::{Function, Operator, Token};
+use crate::lang::tokenizer::Token::Number;
+
+impl Possible {
+    pub fn derive(&self, var: &str) -> Result<Possible, String> {
+        match self {
+            PossToken(Token::Name(name)) if name == var => Ok(number(1.0)),
+            PossToken(Token::Name(_)) | PossToken(Number(_)) | PossToken(Token::Decimal(_)) | PossToken(Token::Integer(_)) => Ok(number(0.0)),
+            PossToken(token) => Err(format!("Can't derive token {:?}", token)),
+            PossCall(function, args) => {
+                let u = match &args[..] {
+                    [u] => u,
+                    _ => return Err(format!("Can't derive {} with {} arguments", function, args.len())),
+                };
+                Ok(node(derive_function(function, u)?, Operator::Multiply, u.derive(var)?))
+            }
+            PossExpression(expression) => derive_expression(expression, var),
+        }
+    }
+
+    pub fn contains(&self, var: &str) -> bool {
+        match self {
+            PossToken(Token::Name(name)) => name == var,
+            PossToken(_) => false,
+            PossCall(_, args) => args.iter().any(|arg| arg.contains(var)),
+            PossExpression(expression) => expression.a.contains(var) || expression.b.contains(var),
+        }
+    }
+}
+
+fn derive_expression(expression: &Expression, var: &str) -> Result<Possible, String> {
+    let a = *expression.a.clone();
+    let b = *expression.b.clone();
+    Ok(match expression.o {
+        // (a ± b)' = a' ± b'
+        Operator::Plus | Operator::Minus => node(a.derive(var)?, expression.o.clone(), b.derive(var)?),
+        // (a * b)' = a' * b + a * b'
+        Operator::Multiply => node(
+            node(a.derive(var)?, Operator::Multiply, b.clone()),
+            Operator::Plus,
+            node(a.clone(), Operator::Multiply, b.derive(var)?),
+        ),
+        // (a / b)' = (a' * b - a * b') / b^2
+        Operator::Divide => node(
+            node(
+                node(a.derive(var)?, Operator::Multiply, b.clone()),
+                Operator::Minus,
+                node(a.clone(), Operator::Multiply, b.derive(var)?),
+            ),
+            Operator::Divide,
+            node(b.clone(), Operator::Exponent, number(2.0)),
+        ),
+        Operator::Exponent => {
+            if !b.contains(var) {
+                // (a^n)' = n * a^(n - 1) * a'
+                node(
+                    node(b.clone(), Operator::Multiply, node(a.clone(), Operator::Exponent, node(b.clone(), Operator::Minus, number(1.0)))),
+                    Operator::Multiply,
+                    a.derive(var)?,
+                )
+            } else if !a.contains(var) {
+                // (c^b)' = c^b * ln(c) * b'
+                node(
+                    node(node(a.clone(), Operator::Exponent, b.clone()), Operator::Multiply, call(Function::Ln, a.clone())),
+                    Operator::Multiply,
+                    b.derive(var)?,
+                )
+            } else {
+                // (a^b)' = a^b * (b' * ln(a) + b * a' / a)
+                node(
+                    node(a.clone(), Operator::Exponent, b.clone()),
+                    Operator::Multiply,
+                    node(
+                        node(b.derive(var)?, Operator::Multiply, call(Function::Ln, a.clone())),
+                        Operator::Plus,
+                        node(node(b.clone(), Operator::Multiply, a.derive(var)?), Operator::Divide, a.clone()),
+                    ),
+                )
+            }
+        }
+        // a // b is the logarithm of a in base b: ln(a) / ln(b)
+        Operator::Log => node(call(Function::Ln, a), Operator::Divide, call(Function::Ln, b)).derive(var)?,
+        // Only constant factorials, n! has no derivative
+        Operator::Factorial if !a.contains(var) => number(0.0),
+        _ => return Err(format!("Can't derive operator {}", expression.o)),
+    })
+}
+
+// Derivative of the function with respect to its argument u, the caller multiplies it by u'
+fn derive_function(function: &Function, u: &Possible) -> Result<Possible, String> {
+    let u = u.clone();
+    Ok(match function {
+        Function::Sin => call(Function::Cos, u),
+        Function::Cos => node(number(-1.0), Operator::Multiply, call(Function::Sin, u)),
+        Function::Tan => node(number(1.0), Operator::Divide, node(call(Function::Cos, u), Operator::Exponent, number(2.0))),
+        Function::Asin => node(number(1.0), Operator::Divide, call(Function::Sqrt, one_minus_square(u))),
+        Function::Acos => node(number(-1.0), Operator::Divide, call(Function::Sqrt, one_minus_square(u))),
+        Function::Atan => node(number(1.0), Operator::Divide, node(number(1.0), Operator::Plus, node(u, Operator::Exponent, number(2.0)))),
+        Function::Sqrt => node(number(1.0), Operator::Divide, node(number(2.0), Operator::Multiply, call(Function::Sqrt, u))),
+        Function::Ln => node(number(1.0), Operator::Divide, u),
+        Function::Exp => call(Function::Exp, u),
+        Function::Abs => node(u.clone(), Operator::Divide, call(Function::Abs, u)),
+        _ => return Err(format!("Can't derive {}", function)),
+    })
+}
+
+fn one_minus_square(u: Possible) -> Possible {
+    node(number(1.0), Operator::Minus, node(u, Operator::Exponent, number(2.0)))
+}
+
+fn node(a: Possible, o: Operator, b: Possible) -> Possible {
+    Possible::expression(Expression::new(a, o, b))
+}
+
+fn call(function: Function, arg: Possible) -> Possible {
+    PossCall(function, vec![arg])
+}
+
+fn number(num: f64) -> Possible {
+    PossToken(Number(num))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::lang::calculator::parse_expression;
+    use crate::lang::tokenizer::tokenize2;
+    use crate::lang::value::Value;
+
+    fn derive(input: &str) -> String {
+        parse_expression(tokenize2(input).unwrap()).unwrap().derive("x").unwrap().simplify().to_string()
+    }
+
+    // Value of an expression of x
+    fn value(input: &str, x: f64) -> f64 {
+        let memory = HashMap::from([("x".to_string(), Value::Number(x))]);
+        parse_expression(tokenize2(input).unwrap()).unwrap().substitute(&memory).unwrap().solve().unwrap().number().unwrap()
+    }
+
+    #[test]
+    fn derives_polynomials() {
+        assert_eq!(derive("x^3 + 2x + 5"), "3 * x ^ 2 + 2");
+        assert_eq!(derive("y * x"), "y");
+        assert_eq!(derive("7"), "0");
+    }
+
+    #[test]
+    fn derives_functions_with_the_chain_rule() {
+        assert_eq!(derive("sin(x)"), "cos(x)");
+        assert_eq!(derive("exp(2x)"), "2 * exp(2 * x)");
+    }
+
+    #[test]
+    fn matches_finite_differences() {
+        let expressions = ["sin(x) * cos(x)", "x / (1 + x^2)", "tan(x)", "asin(x)", "acos(x)", "atan(x^2)",
+            "sqrt(1 + x)", "ln(x^2 + 1)", "exp(-x) * x", "abs(x - 2)", "2^x", "x^x", "x // 3"];
+        for expression in expressions {
+            let derivative = parse_expression(tokenize2(expression).unwrap()).unwrap().derive("x").unwrap().simplify().to_string();
+            for x in [0.3, 0.7] {
+                let h = 1e-6;
+                let expected = (value(expression, x + h) - value(expression, x - h)) / (2.0 * h);
+                let actual = value(&derivative, x);
+                assert!((actual - expected).abs() < 1e-5, "{}: {} instead of {} at {}", expression, actual, expected, x);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_what_it_cant_derive() {
+        assert!(parse_expression(tokenize2("max(x, 2)").unwrap()).unwrap().derive("x").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+/*
+The formatter prints a script back in a canonical spacing style:
+binary operators, comparators and assignments are surrounded by spaces,
+implicit multiplications stay packed ("2b", "2(a + 1)") and parenthesis around a single token are dropped
+ */
+
+use std::f64::consts::{E, PI};
+use std::fmt;
+use crate::lang::calculator::{post_process_paren, Expression, Possible};
+use crate::lang::line_type::{construct_line_type, Condition, LineType};
+use crate::lang::tokenizer::{is_special, tokenize2, Keyword, Operator, Token};
+
+pub fn format_script(script: &str) -> Result<String, String> {
+    let mut formatted = String::new();
+    for (i, line) in script.lines().enumerate() {
+        let line = format_line(line).map_err(|err| format!("line {}: {}", i + 1, err))?;
+        formatted.push_str(&line);
+        formatted.push('\n');
+    }
+    Ok(formatted)
+}
+
+pub fn format_line(line: &str) -> Result<String, String> {
+    let tokens = post_process_paren(tokenize2(line)?)?;
+    format_tokens_line(tokens)
+}
+
+fn format_tokens_line(tokens: Vec<Token>) -> Result<String, String> {
+    if tokens.is_empty() {
+        return Ok(String::new())
+    }
+    Ok(match construct_line_type(tokens.clone())? {
+        // Not a valid instruction, keep the tokens as they are rather than losing them
+        LineType::Nothing => format_tokens(&tokens),
+        LineType::Out(names) => names.join(": "),
+        LineType::VarOperate(var, op, ex) => {
+            format!("{} {} {}", var, op, format_tokens(&post_process_paren(ex)?))
+        }
+        LineType::If(condition, actions) => format_block(Keyword::If, &condition, actions)?,
+        LineType::While(condition, actions) => format_block(Keyword::While, &condition, actions)?,
+        LineType::Solve(var, a, b) => format!("{} {}: {} == {}", Keyword::Solve, var, format_tokens(&a), format_tokens(&b)),
+    })
+}
+
+fn format_block(keyword: Keyword, condition: &Condition, actions: Vec<Vec<Token>>) -> Result<String, String> {
+    let mut result = format!("{} {}:", keyword, format_tokens(&condition.tokens()));
+    let mut first = true;
+    for action in actions {
+        if !first {
+            result.push(':');
+        }
+        first = false;
+        result.push(' ');
+        result.push_str(&format_tokens_line(action)?);
+    }
+    Ok(result)
+}
+
+pub fn format_tokens(tokens: &[Token]) -> String {
+    let mut result = String::new();
+    let mut previous: Option<&Token> = None;
+    let mut unary = false;
+    for token in tokens {
+        let text = match (previous, token) {
+            // Function arguments are always written between parenthesis
+            (Some(Token::Function(_)), Token::Paren(_)) => format_token(token),
+            (Some(Token::Function(_)), token) => format!("({})", format_token(token)),
+            _ => format_token(token),
+        };
+        if let Some(previous) = previous {
+            if !unary {
+                result.push_str(separator(previous, token));
+            }
+            // Never glue two words or numbers together, they would be read back as one token
+            if would_merge(result.chars().last(), text.chars().next()) {
+                result.push(' ');
+            }
+        }
+        // A minus sign is unary when it doesn't follow a value
+        unary = *token == Token::Operator(Operator::Minus)
+            && !matches!(previous, Some(Token::Name(_) | Token::Number(_) | Token::Decimal(_) | Token::Integer(_) | Token::Operator(Operator::Factorial) | Token::ParenClose | Token::Paren(_) | Token::BracketClose | Token::Bracket(_) | Token::List(_) | Token::Matrix(_)));
+        result.push_str(&text);
+        previous = Some(token);
+    }
+    result
+}
+
+pub fn format_token(token: &Token) -> String {
+    match token {
+        Token::Name(name) => name.clone(),
+        Token::Number(num) => format_number(*num),
+        Token::Decimal(num) => num.to_string(),
+        Token::Integer(num) => num.to_string(),
+        Token::Compare(c) => c.to_string(),
+        Token::Operator(o) => o.to_string(),
+        Token::Operation(o) => o.to_string(),
+        Token::Colon => ":".to_string(),
+        Token::Comma => ",".to_string(),
+        Token::Key(keyword) => keyword.to_string(),
+        Token::Function(function) => function.to_string(),
+        Token::ParenOpen => "(".to_string(),
+        Token::ParenClose => ")".to_string(),
+        Token::Paren(tokens) => format!("({})", format_tokens(tokens)),
+        Token::BracketOpen => "[".to_string(),
+        Token::BracketClose => "]".to_string(),
+        Token::Bracket(tokens) => format!("[{}]", format_tokens(tokens)),
+        Token::List(list) => format_list(list),
+        Token::Matrix(matrix) => {
+            let rows: Vec<String> = matrix.iter().map(|row| format_list(row)).collect();
+            format!("[{}]", rows.join(", "))
+        }
+    }
+}
+
+fn format_list(list: &[f64]) -> String {
+    let elements: Vec<String> = list.iter().map(|num| format_number(*num)).collect();
+    format!("[{}]", elements.join(", "))
+}
+
+// The tokenizer turns constants into numbers, print them back under their name
+pub fn format_number(num: f64) -> String {
+    if num == PI {
+        "PI".to_string()
+    } else if num == E {
+        "e".to_string()
+    } else {
+        format!("{}", num)
+    }
+}
+
+fn separator(previous: &Token, current: &Token) -> &'static str {
+    match (previous, current) {
+        (_, Token::Operator(Operator::Factorial)) => "",
+        (_, Token::Operator(_) | Token::Compare(_) | Token::Operation(_)) => " ",
+        (Token::Operator(_) | Token::Compare(_) | Token::Operation(_), _) => " ",
+        (_, Token::Colon | Token::Comma) => "",
+        (Token::ParenOpen | Token::BracketOpen, _) | (_, Token::ParenClose | Token::BracketClose) => "",
+        // Index of a list: "l[0]", "[1, 2][i]"
+        (Token::Name(_) | Token::Paren(_) | Token::ParenClose | Token::Bracket(_) | Token::BracketClose | Token::List(_) | Token::Matrix(_), Token::Bracket(_) | Token::BracketOpen) => "",
+        (Token::Function(_), _) => "",
+        // Implicit multiplications are kept packed when it doesn't merge two tokens
+        (Token::Number(_) | Token::Decimal(_) | Token::Integer(_), Token::Name(_)) => "",
+        (Token::Number(_) | Token::Decimal(_) | Token::Integer(_) | Token::Name(_) | Token::ParenClose | Token::Paren(_), Token::ParenOpen | Token::Paren(_)) => "",
+        (Token::ParenClose | Token::Paren(_), Token::Name(_) | Token::Number(_) | Token::Decimal(_) | Token::Integer(_)) => "",
+        _ => " ",
+    }
+}
+
+// A word swallows the letters and digits after it, a number swallows the digits after it
+// and operators are read as a single run of special chars ("=-" is not "=" then "-")
+pub fn would_merge(last: Option<char>, next: Option<char>) -> bool {
+    match (last, next) {
+        (Some(last), Some(next)) => {
+            if last.is_alphabetic() || last == '_' {
+                next.is_alphanumeric() || next == '_'
+            } else if last.is_numeric() || last == '.' {
+                next.is_numeric() || next == '.'
+            } else if is_special(last) {
+                is_special(next)
+            } else {
+                false
+            }
+        }
+        _ => false,
+    }
+}
+
+// Print an expression three back as source, only with the parenthesis the priorities need
+impl fmt::Display for Possible {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Possible::PossToken(token) => write!(f, "{}", format_token(token)),
+            Possible::PossExpression(expression) => write!(f, "{}", expression),
+            Possible::PossCall(function, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", function, args.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // "-1 * a" is how the calculator reads "-a"
+        if self.o == Operator::Multiply && *self.a == Possible::PossToken(Token::Number(-1.0)) {
+            if let Possible::PossToken(Token::Name(_)) | Possible::PossCall(_, _) = &*self.b {
+                return write!(f, "-{}", self.b)
+            }
+        }
+        let a = match &*self.a {
+            Possible::PossExpression(a) if a.o.priority() < self.o.priority() => format!("({})", a),
+            a => a.to_string(),
+        };
+        // "n!" is read as "n ! 0", "(-3)!" keeps its parenthesis
+        if self.o == Operator::Factorial {
+            return match a.starts_with('-') {
+                true => write!(f, "({})!", a),
+                false => write!(f, "{}!", a),
+            }
+        }
+        let b = match &*self.b {
+            // Operations are read from left to right, a right branch of the same priority needs parenthesis
+            Possible::PossExpression(b) if b.o.priority() < self.o.priority()
+                || (b.o.priority() == self.o.priority() && !(b.o == self.o && matches!(self.o, Operator::Plus | Operator::Multiply))) => format!("({})", b),
+            b => b.to_string(),
+        };
+        write!(f, "{} {} {}", a, self.o, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spaces_binary_operators_and_assignments() {
+        assert_eq!(format_line("a=1+2*b").unwrap(), "a = 1 + 2 * b");
+        assert_eq!(format_line("x+=  3").unwrap(), "x += 3");
+        assert_eq!(format_line("c = a-b").unwrap(), "c = a - b");
+    }
+
+    #[test]
+    fn keeps_implicit_multiplications_packed() {
+        assert_eq!(format_line("y = 2 * (a + 1)").unwrap(), "y = 2 * (a + 1)");
+        assert_eq!(format_line("y = 2(a+1)").unwrap(), "y = 2(a + 1)");
+        assert_eq!(format_line("y = 3 x").unwrap(), "y = 3x");
+    }
+
+    #[test]
+    fn keeps_unary_minus_attached() {
+        assert_eq!(format_line("a = -b * 2").unwrap(), "a = -b * 2");
+        assert_eq!(format_line("a = 3 * -2").unwrap(), "a = 3 * -2");
+    }
+
+    #[test]
+    fn drops_parenthesis_around_a_single_token() {
+        assert_eq!(format_line("a = ((b)) + (2)").unwrap(), "a = b + 2");
+    }
+
+    #[test]
+    fn formats_blocks_and_outputs() {
+        assert_eq!(format_line("if a>=2:b=1:b").unwrap(), "if a >= 2: b = 1: b");
+        assert_eq!(format_line("while 0<i<10:i+=1").unwrap(), "while 0 < i < 10: i += 1");
+        assert_eq!(format_line("a:b").unwrap(), "a: b");
+    }
+
+    #[test]
+    fn prints_constants_by_name() {
+        assert_eq!(format_line("a = 2*PI").unwrap(), "a = 2 * PI");
+        assert_eq!(format_line("a = 2 e").unwrap(), "a = 2 e");
+    }
+
+    #[test]
+    fn is_idempotent() {
+        let script = "a = 1 + 2b\nif a != 3: a\nb = sin(a)^2 + l[0]\n";
+        let formatted = format_script(script).unwrap();
+        assert_eq!(format_script(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn writes_the_parenthesis_of_an_expression_tree() {
+        let written = |input: &str| crate::lang::calculator::parse_expression(tokenize2(input).unwrap()).unwrap().to_string();
+        assert_eq!(written("a / (b * c)"), "a / (b * c)");
+        assert_eq!(written("a * (b % 3)"), "a * (b % 3)");
+        assert_eq!(written("a * (b / c)"), "a * (b / c)");
+        assert_eq!(written("a - (b + c)"), "a - (b + c)");
+        assert_eq!(written("a + (b + c) * 2"), "a + (b + c) * 2");
+        assert_eq!(written("a * (b * c)"), "a * b * c");
+    }
+
+    #[test]
+    fn reports_the_line_of_an_error() {
+        assert!(format_script("a = 1\nb = (2\n").unwrap_err().starts_with("line 2"));
+    }
+}
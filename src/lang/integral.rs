@@ -0,0 +1,92 @@
+/*
+Adaptive Simpson quadrature: each interval is split in two until the two halves
+agree with the whole interval within the tolerance
+ */
+
+const TOLERANCE: f64 = 1e-10;
+const MAX_DEPTH: usize = 40;
+// Bounds the work on functions that never meet the tolerance (discontinuities, large values)
+const MAX_EVALUATIONS: usize = 1_000_000;
+
+pub fn integrate<F: FnMut(f64) -> Result<f64, String>>(mut f: F, a: f64, b: f64) -> Result<f64, String> {
+    if a == b {
+        return Ok(0.0)
+    }
+    let mut evaluations = 0;
+    let mut f = |x: f64| {
+        evaluations += 1;
+        if evaluations > MAX_EVALUATIONS {
+            return Err(format!("Integral from {} to {} doesn't converge", a, b))
+        }
+        f(x)
+    };
+    let m = (a + b) / 2.0;
+    let (fa, fm, fb) = (f(a)?, f(m)?, f(b)?);
+    let whole = simpson(a, b, fa, fm, fb);
+    let result = adaptive(&mut f, (a, fa), (m, fm), (b, fb), whole, TOLERANCE, MAX_DEPTH)?;
+    if result.is_finite() {
+        Ok(result)
+    } else {
+        Err(format!("Integral from {} to {} doesn't converge", a, b))
+    }
+}
+
+fn simpson(a: f64, b: f64, fa: f64, fm: f64, fb: f64) -> f64 {
+    (b - a) / 6.0 * (fa + 4.0 * fm + fb)
+}
+
+fn adaptive<F: FnMut(f64) -> Result<f64, String>>(f: &mut F, (a, fa): (f64, f64), (m, fm): (f64, f64), (b, fb): (f64, f64),
+                                                   whole: f64, tolerance: f64, depth: usize) -> Result<f64, String> {
+    let (left_m, right_m) = ((a + m) / 2.0, (m + b) / 2.0);
+    let (f_left_m, f_right_m) = (f(left_m)?, f(right_m)?);
+    let left = simpson(a, m, fa, f_left_m, fm);
+    let right = simpson(m, b, fm, f_right_m, fb);
+    let delta = left + right - whole;
+    // The tolerance can't go below the precision of the result itself
+    if depth == 0 || delta.abs() <= 15.0 * tolerance.max(f64::EPSILON * (left + right).abs()) {
+        // Richardson extrapolation of the two estimates
+        return Ok(left + right + delta / 15.0)
+    }
+    Ok(adaptive(f, (a, fa), (left_m, f_left_m), (m, fm), left, tolerance / 2.0, depth - 1)?
+        + adaptive(f, (m, fm), (right_m, f_right_m), (b, fb), right, tolerance / 2.0, depth - 1)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} instead of {}", actual, expected);
+    }
+
+    #[test]
+    fn integrates_polynomials_exactly() {
+        assert_close(integrate(|x| Ok(x * x), 0.0, 3.0).unwrap(), 9.0);
+        assert_close(integrate(|x| Ok(x.powi(3) - 2.0 * x), -1.0, 2.0).unwrap(), 0.75);
+    }
+
+    #[test]
+    fn integrates_smooth_functions() {
+        assert_close(integrate(|x| Ok(x.sin()), 0.0, PI).unwrap(), 2.0);
+        assert_close(integrate(|x| Ok(1.0 / x), 1.0, 10.0).unwrap(), 10f64.ln());
+        assert_close(integrate(|x| Ok((-x * x).exp()), -10.0, 10.0).unwrap(), PI.sqrt());
+    }
+
+    #[test]
+    fn handles_reversed_and_empty_intervals() {
+        assert_close(integrate(|x| Ok(2.0 * x), 2.0, 0.0).unwrap(), -4.0);
+        assert_eq!(integrate(|x| Ok(x * x), 1.0, 1.0).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn integrates_kinks() {
+        assert_close(integrate(|x| Ok(x.abs()), -1.0, 2.0).unwrap(), 2.5);
+    }
+
+    #[test]
+    fn reports_errors_and_divergence() {
+        assert!(integrate(|x| Ok(1.0 / x), 0.0, 1.0).is_err());
+        assert!(integrate(|_| Err("no".to_string()), 0.0, 1.0).is_err());
+    }
+}
@@ -8,16 +8,51 @@ pub enum LineType {
     Nothing,
     Out(Vec<String>),
     VarOperate(String, Operation, Vec<Token>), // (var_name, operator, expression)
-    If(Vec<Token>, Compare, Vec<Token>, Vec<Vec<Token>>), // (expression, comparator, expression, lines
-    While(Vec<Token>, Compare, Vec<Token>, Vec<Vec<Token>>),
+    If(Condition, Vec<Vec<Token>>), // (condition, lines)
+    While(Condition, Vec<Vec<Token>>),
+    Solve(String, Vec<Token>, Vec<Token>), // (var_name, expression, expression) of "solve x: a == b"
     //Function, TODO
 }
 
+// Comparisons chained from left to right, "0 < x <= 10" is "0 < x and x <= 10"
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub first: Vec<Token>,
+    pub rest: Vec<(Compare, Vec<Token>)>,
+}
+
+impl Condition {
+    // None when there is no comparator
+    pub fn parse(tokens: &[Token]) -> Option<Condition> {
+        let mut parts = tokens.split(|token| matches!(token, Token::Compare(_)));
+        let first = parts.next()?.to_vec();
+        let compares = tokens.iter().filter_map(|token| match token {
+            Token::Compare(c) => Some(c.clone()),
+            _ => None,
+        });
+        let rest: Vec<(Compare, Vec<Token>)> = compares.zip(parts.map(|part| part.to_vec())).collect();
+        if rest.is_empty() { None } else { Some(Condition { first, rest }) }
+    }
+
+    pub fn operands(&self) -> impl Iterator<Item = &Vec<Token>> {
+        std::iter::once(&self.first).chain(self.rest.iter().map(|(_, operand)| operand))
+    }
+
+    // Tokens of the condition as written
+    pub fn tokens(&self) -> Vec<Token> {
+        let mut tokens = self.first.clone();
+        for (c, operand) in &self.rest {
+            tokens.push(Token::Compare(c.clone()));
+            tokens.extend(operand.iter().cloned());
+        }
+        tokens
+    }
+}
+
 pub fn construct_line_type(tokens: Vec<Token>) -> Result<LineType, String> {
-    let tokens = tokens;
-    if tokens.len() == 0 {return Ok(Nothing)  }
+    if tokens.is_empty() {return Ok(Nothing)  }
 
-    if let Token::Name(name) = tokens.get(0).unwrap() {
+    if let Token::Name(name) = tokens.first().unwrap() {
         // Check if its a print "a: b: c:" <=> print(a,b,c) "a:" <=> print(a)
         if tokens.len() == 1 {
             return Ok(Out(vec![name.clone()]));
@@ -35,76 +70,63 @@ pub fn construct_line_type(tokens: Vec<Token>) -> Result<LineType, String> {
         }
         // Check for var assignment
         if tokens.len() >= 3 {
-            match tokens.get(1).unwrap() {
-                Token::Operation(o) =>  {
-                    return Ok(VarOperate(name.clone(), o.clone(), tokens[2..].to_owned()))
-                }
-                _ => {}
+            if let Token::Operation(o) = tokens.get(1).unwrap() {
+                return Ok(VarOperate(name.clone(), o.clone(), tokens[2..].to_owned()))
             }
         }
     }
 
-    if let Token::Key(keyword) = tokens.get(0).unwrap() {
+    if let Token::Key(keyword) = tokens.first().unwrap().clone() {
         match keyword {
             Keyword::If => {
-                let tokens = post_process_paren(tokens)?;
-                let mut tmp: Option<(usize, Compare)> = None;
-                for i in 1..tokens.len() {
-                    if let Token::Compare(compare) = tokens.get(i).unwrap() {
-                        tmp = Some((i, compare.clone()))
-                    }
-                }
-                let (i, comp) = tmp.expect("No comparator in if statement");
-
-                let mut limit: Option<usize> = None;
-                for i in i+1..tokens.len() {
-                    if let Token::Colon = tokens.get(i).unwrap() {
-                        limit = Some(i);
-                        break;
-                    }
-                }
-                let limit = limit.expect("No consequence to if");
-                let actions_vec = post_process_paren(Vec::from(tokens.get(limit+1..tokens.len()).unwrap()))?;
-                let splits = actions_vec.split(|x| x.clone() == Token::Colon)
-                    .filter(|&subvec| subvec != [Token::Colon])
-                    .map(|subvec| subvec.to_vec())
-                    .collect();
-                return Ok(If(Vec::from(tokens.get(1..i).unwrap()), comp, Vec::from(tokens.get(i+1..limit).unwrap()), splits))
+                let (condition, actions) = block(tokens, &keyword)?;
+                return Ok(If(condition, actions))
             }
             Keyword::While => {
+                let (condition, actions) = block(tokens, &keyword)?;
+                return Ok(LineType::While(condition, actions))
+            }
+            Keyword::Solve => {
                 let tokens = post_process_paren(tokens)?;
-                let mut tmp: Option<(usize, Compare)> = None;
-                for i in 1..tokens.len() {
-                    if let Token::Compare(compare) = tokens.get(i).unwrap() {
-                        tmp = Some((i, compare.clone()))
-                    }
+                if let [_, Token::Name(var), Token::Colon, equation @ ..] = &tokens[..] {
+                    let pos = equation.iter().position(|token| *token == Token::Compare(Compare::Equal))
+                        .ok_or("No == in solve statement")?;
+                    return Ok(LineType::Solve(var.clone(), equation[..pos].to_vec(), equation[pos+1..].to_vec()))
                 }
-                let (pos, comp) = tmp.expect("No comparator in if statement");
-
-                let mut limit: Option<usize> = None;
-                for i in pos + 1..tokens.len() {
-                    if let Token::Colon = tokens.get(i).unwrap() {
-                        limit = Some(i);
-                        break;
-                    }
-                }
-                let limit = limit.expect("No consequence to while");
-                let actions_vec = Vec::from(tokens.get(limit+1..tokens.len()).unwrap());
-
-                let splits = actions_vec.split(|x| x.clone() == Token::Colon)
-                    .filter(|&subvec| subvec != [Token::Colon])
-                    .map(|subvec| subvec.to_vec())
-                    .collect();
-                return Ok(LineType::While(Vec::from(tokens.get(1..pos).unwrap()), comp, Vec::from(tokens.get(pos+1..limit).unwrap()), splits))
+                return Err("Expected solve <var>: <expression> == <expression>".into())
             }
         }
     }
 
-    if let Token::Paren(toks) = tokens.get(0).unwrap() {
+    if let Token::Paren(toks) = tokens.first().unwrap() {
         return construct_line_type(toks.clone())
     }
 
-    return Ok(Nothing)
+    Ok(Nothing)
+}
+
+// The actions of a body are separated by ":"
+fn split_actions(tokens: &[Token]) -> Vec<Vec<Token>> {
+    let mut actions: Vec<Vec<Token>> = Vec::new();
+    for subvec in tokens.split(|x| x.clone() == Token::Colon).filter(|&subvec| subvec != [Token::Colon]) {
+        match actions.last_mut() {
+            // The ":" of "solve x: a == b" doesn't end the action
+            Some(last) if matches!(&last[..], [Token::Key(Keyword::Solve), Token::Name(_)]) => {
+                last.push(Token::Colon);
+                last.extend(subvec.iter().cloned());
+            }
+            _ => actions.push(subvec.to_vec()),
+        }
+    }
+    actions
+}
 
-    ;
+// "<keyword> <condition>: <line>: <line>"
+fn block(tokens: Vec<Token>, keyword: &Keyword) -> Result<(Condition, Vec<Vec<Token>>), String> {
+    let tokens = post_process_paren(tokens)?;
+    let limit = tokens.iter().position(|token| *token == Token::Colon)
+        .ok_or(format!("No consequence to {}", keyword))?;
+    let condition = Condition::parse(&tokens[1..limit])
+        .ok_or(format!("No comparator in {} statement", keyword))?;
+    Ok((condition, split_actions(&tokens[limit+1..])))
 }
@@ -0,0 +1,198 @@
+/*
+Linear algebra on matrices stored as a list of rows.
+The determinant, the inverse and the linear systems use a Gaussian elimination with partial pivoting
+ */
+
+// Below that pivot (relatively to the largest element) the matrix is considered singular
+const SINGULAR: f64 = 1e-12;
+// Largest power of a matrix, computed with about 30 squarings
+const MAX_POWER: f64 = 1e9;
+
+pub fn multiply(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, String> {
+    if columns(a) != b.len() {
+        return Err(format!("Can't multiply a {} matrix by a {} matrix", size(a), size(b)))
+    }
+    Ok(a.iter().map(|row| {
+        (0..columns(b)).map(|j| row.iter().zip(b).map(|(x, b_row)| x * b_row[j]).sum()).collect()
+    }).collect())
+}
+
+pub fn transpose(a: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    (0..columns(a)).map(|j| a.iter().map(|row| row[j]).collect()).collect()
+}
+
+pub fn identity(n: usize) -> Vec<Vec<f64>> {
+    (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect()
+}
+
+pub fn determinant(a: &[Vec<f64>]) -> Result<f64, String> {
+    square(a, "determinant")?;
+    let mut a = a.to_vec();
+    let mut determinant = 1.0;
+    for k in 0..a.len() {
+        let pivot = match pivot(&a, k) {
+            Some(pivot) => pivot,
+            None => return Ok(0.0),
+        };
+        if pivot != k {
+            a.swap(pivot, k);
+            determinant = -determinant;
+        }
+        determinant *= a[k][k];
+        eliminate(&mut a, k);
+    }
+    Ok(determinant)
+}
+
+pub fn inverse(a: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, String> {
+    square(a, "inverse")?;
+    solve_matrix(a, identity(a.len()))
+}
+
+// a^n by squaring, a negative power is a power of the inverse
+pub fn power(a: &[Vec<f64>], n: f64) -> Result<Vec<Vec<f64>>, String> {
+    square(a, "power")?;
+    if n.fract() != 0.0 || n.abs() > MAX_POWER {
+        return Err(format!("Matrix power {} is not an integer up to {}", n, MAX_POWER))
+    }
+    let mut base = if n < 0.0 { inverse(a)? } else { a.to_vec() };
+    let mut power = identity(a.len());
+    let mut exponent = n.abs() as u64;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            power = multiply(&power, &base)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = multiply(&base, &base)?;
+        }
+    }
+    Ok(power)
+}
+
+// x such that a * x = b
+pub fn solve(a: &[Vec<f64>], b: &[f64]) -> Result<Vec<f64>, String> {
+    square(a, "linear system")?;
+    if a.len() != b.len() {
+        return Err(format!("A {} matrix needs a right-hand side of {} values, {} given", size(a), a.len(), b.len()))
+    }
+    let x = solve_matrix(a, b.iter().map(|x| vec![*x]).collect())?;
+    Ok(x.into_iter().map(|row| row[0]).collect())
+}
+
+// Gauss-Jordan elimination of [a | b], b becomes a^-1 * b
+fn solve_matrix(a: &[Vec<f64>], b: Vec<Vec<f64>>) -> Result<Vec<Vec<f64>>, String> {
+    let n = a.len();
+    let mut augmented: Vec<Vec<f64>> = a.iter().zip(b).map(|(row, b_row)| [&row[..], &b_row[..]].concat()).collect();
+    for k in 0..n {
+        let pivot = pivot(&augmented[..], k).ok_or("Singular matrix")?;
+        augmented.swap(pivot, k);
+        let value = augmented[k][k];
+        for x in augmented[k].iter_mut() {
+            *x /= value;
+        }
+        for i in 0..n {
+            if i != k {
+                let factor = augmented[i][k];
+                for j in k..augmented[i].len() {
+                    augmented[i][j] -= factor * augmented[k][j];
+                }
+            }
+        }
+    }
+    Ok(augmented.into_iter().map(|row| row[n..].to_vec()).collect())
+}
+
+// Row with the largest value in column k from row k, None if they are all (nearly) zero
+fn pivot(a: &[Vec<f64>], k: usize) -> Option<usize> {
+    let scale = a.iter().flat_map(|row| row[..a.len()].iter()).fold(0.0, |max: f64, x| max.max(x.abs()));
+    let pivot = (k..a.len()).max_by(|i, j| a[*i][k].abs().total_cmp(&a[*j][k].abs()))?;
+    if a[pivot][k].abs() <= SINGULAR * scale || a[pivot][k] == 0.0 {
+        return None
+    }
+    Some(pivot)
+}
+
+// Cancel the column k under the pivot
+fn eliminate(a: &mut [Vec<f64>], k: usize) {
+    for i in k + 1..a.len() {
+        let factor = a[i][k] / a[k][k];
+        for j in k..a[i].len() {
+            a[i][j] -= factor * a[k][j];
+        }
+    }
+}
+
+fn square(a: &[Vec<f64>], operation: &str) -> Result<(), String> {
+    if a.len() != columns(a) {
+        return Err(format!("The {} needs a square matrix, not a {} one", operation, size(a)))
+    }
+    Ok(())
+}
+
+fn columns(a: &[Vec<f64>]) -> usize {
+    a.first().map_or(0, |row| row.len())
+}
+
+pub fn size(a: &[Vec<f64>]) -> String {
+    format!("{}x{}", a.len(), columns(a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[Vec<f64>], b: &[Vec<f64>]) {
+        assert_eq!(size(a), size(b));
+        for (row_a, row_b) in a.iter().zip(b) {
+            for (x, y) in row_a.iter().zip(row_b) {
+                assert!((x - y).abs() < 1e-9, "{:?} instead of {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn inverts_matrices() {
+        let a = vec![vec![4.0, 7.0], vec![2.0, 6.0]];
+        assert_close(&inverse(&a).unwrap(), &[vec![0.6, -0.7], vec![-0.2, 0.4]]);
+        // Needs a row swap, the first pivot is zero
+        let b = vec![vec![0.0, 1.0, 2.0], vec![1.0, 0.0, 3.0], vec![4.0, -3.0, 8.0]];
+        assert_close(&multiply(&b, &inverse(&b).unwrap()).unwrap(), &identity(3));
+    }
+
+    #[test]
+    fn rejects_singular_and_rectangular_matrices() {
+        assert_eq!(inverse(&[vec![1.0, 2.0], vec![2.0, 4.0]]).unwrap_err(), "Singular matrix");
+        assert!(inverse(&[vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]).is_err());
+    }
+
+    #[test]
+    fn computes_determinants() {
+        assert_eq!(determinant(&[vec![1.0, 2.0], vec![3.0, 4.0]]).unwrap(), -2.0);
+        assert!((determinant(&[vec![0.0, 1.0, 2.0], vec![1.0, 0.0, 3.0], vec![4.0, -3.0, 8.0]]).unwrap() + 2.0).abs() < 1e-12);
+        assert_eq!(determinant(&[vec![1.0, 2.0], vec![2.0, 4.0]]).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn solves_linear_systems() {
+        let x = solve(&[vec![2.0, 1.0], vec![1.0, 3.0]], &[3.0, 5.0]).unwrap();
+        assert!((x[0] - 0.8).abs() < 1e-12 && (x[1] - 1.4).abs() < 1e-12);
+        assert!(solve(&[vec![2.0, 1.0], vec![1.0, 3.0]], &[3.0]).is_err());
+    }
+
+    #[test]
+    fn raises_to_powers_by_squaring() {
+        let a = vec![vec![1.0, 1.0], vec![1.0, 0.0]];
+        assert_eq!(power(&a, 10.0).unwrap(), vec![vec![89.0, 55.0], vec![55.0, 34.0]]);
+        assert_close(&power(&a, -2.0).unwrap(), &multiply(&inverse(&a).unwrap(), &inverse(&a).unwrap()).unwrap());
+        assert_eq!(power(&identity(2), 1e9).unwrap(), identity(2));
+        assert_eq!(power(&a, 0.0).unwrap(), identity(2));
+    }
+
+    #[test]
+    fn rejects_invalid_powers() {
+        assert!(power(&[vec![1.0, 2.0, 3.0]], 0.0).is_err());
+        assert!(power(&identity(2), 0.5).is_err());
+        assert!(power(&identity(2), 1e10).is_err());
+    }
+}
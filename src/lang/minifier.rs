@@ -0,0 +1,205 @@
+/*
+The minifier rewrites a script into its shortest equivalent form:
+optional whitespace is dropped, "*" becomes an implicit multiplication where possible,
+"while" becomes "wl" and variables are renamed to the shortest free names, most used first
+ */
+
+use std::collections::HashMap;
+use crate::lang::calculator::{implicit_multiplication, post_process_paren};
+use crate::lang::formatter::{format_token, would_merge};
+use crate::lang::line_type::{construct_line_type, Condition, LineType};
+use crate::lang::tokenizer::{tokenize2, Compare, Operator, Token};
+
+pub fn minify_script(script: &str, rename: bool) -> Result<String, String> {
+    let mut lines = Vec::new();
+    for (i, line) in script.lines().enumerate() {
+        let tokens = tokenize2(line).and_then(post_process_paren).map_err(|err| format!("line {}: {}", i + 1, err))?;
+        // Empty lines are only there for the reader
+        if !tokens.is_empty() {
+            lines.push((i + 1, tokens));
+        }
+    }
+
+    let names = if rename { shortest_names(&lines) } else { HashMap::new() };
+    let mut minified = String::new();
+    for (number, tokens) in lines {
+        let tokens = rename_tokens(tokens, &names);
+        let line = minify_line(tokens).map_err(|err| format!("line {}: {}", number, err))?;
+        minified.push_str(&line);
+        minified.push('\n');
+    }
+    Ok(minified)
+}
+
+fn minify_line(tokens: Vec<Token>) -> Result<String, String> {
+    Ok(match construct_line_type(tokens.clone())? {
+        // Not a valid instruction, keep the tokens as they are rather than losing them
+        LineType::Nothing => minify_tokens(&tokens),
+        LineType::Out(names) => names.join(":"),
+        LineType::VarOperate(var, op, ex) => {
+            let mut line = vec![Token::Name(var), Token::Operation(op)];
+            line.extend(post_process_paren(ex)?);
+            minify_tokens(&line)
+        }
+        LineType::If(condition, actions) => minify_block("if", condition, actions)?,
+        LineType::While(condition, actions) => minify_block("wl", condition, actions)?,
+        LineType::Solve(var, a, b) => {
+            let mut equation = a;
+            equation.push(Token::Compare(Compare::Equal));
+            equation.extend(b);
+            let mut result = format!("solve {}:", var);
+            result.push_str(&minify_tokens(&equation));
+            result
+        }
+    })
+}
+
+fn minify_block(keyword: &str, condition: Condition, actions: Vec<Vec<Token>>) -> Result<String, String> {
+    let mut result = keyword.to_string();
+    push_packed(&mut result, &minify_tokens(&condition.tokens()));
+    result.push(':');
+    let mut first = true;
+    for action in actions {
+        if !first {
+            result.push(':');
+        }
+        first = false;
+        result.push_str(&minify_line(action)?);
+    }
+    Ok(result)
+}
+
+fn minify_tokens(tokens: &[Token]) -> String {
+    let tokens = drop_multiplications(tokens);
+    let mut result = String::new();
+    for token in &tokens {
+        push_packed(&mut result, &minify_token(token));
+    }
+    result
+}
+
+fn minify_token(token: &Token) -> String {
+    match token {
+        Token::Paren(tokens) => format!("({})", minify_tokens(tokens)),
+        Token::Bracket(tokens) => format!("[{}]", minify_tokens(tokens)),
+        token => format_token(token),
+    }
+}
+
+// Append without a space unless the two texts would be read back as a single token
+fn push_packed(result: &mut String, text: &str) {
+    if would_merge(result.chars().last(), text.chars().next()) {
+        result.push(' ');
+    }
+    result.push_str(text);
+}
+
+// "2*a" => "2a": the calculator inserts the same Multiply back, so the expression is unchanged
+fn drop_multiplications(tokens: &[Token]) -> Vec<Token> {
+    let mut result: Vec<Token> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let (Some(previous), Token::Operator(Operator::Multiply), Some(next)) = (result.last(), &tokens[i], tokens.get(i + 1)) {
+            let previous_text = minify_token(previous);
+            let next_text = minify_token(next);
+            if implicit_multiplication(previous, next) && !would_merge(previous_text.chars().last(), next_text.chars().next()) {
+                i += 1;
+                continue;
+            }
+        }
+        result.push(match &tokens[i] {
+            Token::Paren(inner) => Token::Paren(drop_multiplications(inner)),
+            Token::Bracket(inner) => Token::Bracket(drop_multiplications(inner)),
+            token => token.clone(),
+        });
+        i += 1;
+    }
+    result
+}
+
+// Most used variables get the shortest names, names starting with "_" are managed by the interpreter and kept
+fn shortest_names(lines: &[(usize, Vec<Token>)]) -> HashMap<String, String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (_, tokens) in lines {
+        count_names(tokens, &mut counts);
+    }
+    let mut names: Vec<(String, usize)> = counts.into_iter().filter(|(name, _)| !name.starts_with('_')).collect();
+    names.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut candidates = (1..).flat_map(names_of_length).filter(|name| is_free_name(name));
+    names.into_iter().map(|(name, _)| (name, candidates.next().unwrap())).collect()
+}
+
+fn count_names(tokens: &[Token], counts: &mut HashMap<String, usize>) {
+    for token in tokens {
+        match token {
+            Token::Name(name) => *counts.entry(name.clone()).or_insert(0) += 1,
+            Token::Paren(tokens) | Token::Bracket(tokens) => count_names(tokens, counts),
+            _ => {}
+        }
+    }
+}
+
+fn names_of_length(length: usize) -> Vec<String> {
+    let letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+    let mut names = vec![String::new()];
+    for _ in 0..length {
+        names = names.iter().flat_map(|name| letters.iter().map(move |c| format!("{}{}", name, c))).collect();
+    }
+    names
+}
+
+// Keywords and constants ("if", "wl", "e", ...) can't be used as variable names
+fn is_free_name(name: &str) -> bool {
+    matches!(tokenize2(name).as_deref(), Ok([Token::Name(_)]))
+}
+
+fn rename_tokens(tokens: Vec<Token>, names: &HashMap<String, String>) -> Vec<Token> {
+    tokens.into_iter().map(|token| match token {
+        Token::Name(name) => Token::Name(names.get(&name).cloned().unwrap_or(name)),
+        Token::Paren(tokens) => Token::Paren(rename_tokens(tokens, names)),
+        Token::Bracket(tokens) => Token::Bracket(rename_tokens(tokens, names)),
+        token => token,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::run_line;
+    use crate::lang::value::Value;
+
+    // Memory left by a script, the names are those of the script
+    fn run_script(script: &str) -> HashMap<String, Value> {
+        let mut memory = HashMap::new();
+        for line in script.lines() {
+            run_line(line, &mut memory).unwrap();
+        }
+        memory
+    }
+
+    #[test]
+    fn drops_whitespace_and_multiplications() {
+        assert_eq!(minify_script("total = 2 * (count + 1)\n", false).unwrap(), "total=2(count+1)\n");
+        assert_eq!(minify_script("a = 2 * b * c\n", false).unwrap(), "a=2b*c\n");
+    }
+
+    #[test]
+    fn shortens_keywords_and_drops_empty_lines() {
+        assert_eq!(minify_script("i = 0\n\nwhile i < 3: i += 1\n", false).unwrap(), "i=0\nwl i<3:i+=1\n");
+    }
+
+    #[test]
+    fn renames_the_most_used_names_first() {
+        let minified = minify_script("total = 1\ncount = 2\ntotal = total + count\ntotal\n", true).unwrap();
+        assert_eq!(minified, "a=1\nb=2\na=a+b\na\n");
+    }
+
+    #[test]
+    fn keeps_the_results() {
+        let script = "x = 3\ny = 2 * x ^ 2 - -x\nn = 0\nwhile n < 5: n += 1: y -= n\nif y > 10: z = y / 2\n";
+        let minified = minify_script(script, false).unwrap();
+        assert!(minified.len() < script.len());
+        assert_eq!(run_script(&minified), run_script(script));
+    }
+}
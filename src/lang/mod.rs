@@ -1,15 +1,36 @@
-mod tokenizer;
-mod runner;
+pub(crate) mod tokenizer;
+pub(crate) mod runner;
 mod calculator;
-mod line_type;
+pub(crate) mod checker;
+pub(crate) mod formatter;
+pub(crate) mod minifier;
+mod simplifier;
+mod derivative;
+mod solver;
+mod integral;
+mod statistics;
+mod matrix;
+pub(crate) mod random;
+pub(crate) mod backend;
+pub(crate) mod numeric;
+mod bigint;
+pub(crate) mod decimal;
+pub(crate) mod value;
+mod plot;
+mod table;
+pub(crate) mod line_type;
+pub(crate) mod transpile;
 
 use std::collections::HashMap;
-use crate::lang::line_type::construct_line_type;
-use crate::lang::runner::run;
-use crate::lang::tokenizer::tokenize2;
+use crate::lang::calculator::{parse_expression, post_process_paren, split_arguments};
+use crate::lang::formatter::format_tokens;
+use crate::lang::line_type::{construct_line_type, Condition, LineType};
+use crate::lang::runner::{check, result, run};
+use crate::lang::tokenizer::{tokenize2, Token};
+use crate::lang::value::Value;
 
 
-pub fn run_line(line: &str, memory: &mut HashMap<String, f64>) -> Result<LineResult, String> {
+pub fn run_line(line: &str, memory: &mut HashMap<String, Value>) -> Result<LineResult, String> {
     // Transform the line string into tokens
     let tokens = tokenize2(line)?;
     //DEBUG print tokens
@@ -17,12 +38,97 @@ pub fn run_line(line: &str, memory: &mut HashMap<String, f64>) -> Result<LineRes
     let line_type = construct_line_type(tokens)?;
     //println!("{:?}", line_type);
     let line_result = run(memory, line_type)?;
-    return Ok(line_result)
+    Ok(line_result)
 
     // println!("{}", calculate(tokens));
     // LineResult::none()
 }
 
+pub fn parse_line(line: &str) -> Result<LineType, String> {
+    let tokens = tokenize2(line)?;
+    construct_line_type(tokens)
+}
+
+// Evaluate a standalone condition such as "i >= 3" or "0 < i < 10"
+pub fn check_condition(condition: &str, memory: &mut HashMap<String, Value>) -> Result<bool, String> {
+    let tokens = post_process_paren(tokenize2(condition)?)?;
+    match Condition::parse(&tokens) {
+        Some(parsed) => check(memory, &parsed),
+        None => Err(format!("No comparator in condition: {}", condition.trim())),
+    }
+}
+
+// Print the simplified form of an expression, variables are kept as they are
+pub fn simplify_expression(input: &str) -> Result<String, String> {
+    let three = parse_expression(tokenize2(input)?)?;
+    Ok(three.simplify().to_string())
+}
+
+// Print the simplified derivative of an expression and its value when every variable is in memory
+pub fn derive_expression(var: &str, input: &str, memory: &HashMap<String, Value>) -> Result<(String, Option<f64>), String> {
+    let derivative = parse_expression(tokenize2(input)?)?.derive(var)?.simplify();
+    let value = derivative.clone().substitute(memory).and_then(|three| three.solve()?.number()).ok();
+    Ok((derivative.to_string(), value))
+}
+
+// Draw "y = <expression> for <var> in <from>..<to>" in the terminal, the variable isn't stored in memory
+pub fn plot_expression(input: &str, memory: &HashMap<String, Value>) -> Result<String, String> {
+    let (function, range) = input.split_once(" for ").ok_or("Expected plot y = <expression> for <var> in <from>..<to>")?;
+    // "y = " only names the axis
+    let expression = function.split_once('=').map_or(function, |(_, expression)| expression);
+    let mut local = memory.clone();
+    let (var, from, to, _) = parse_range(range, &mut local)?;
+    let tokens = tokenize2(expression)?;
+    plot::plot(|x| {
+        local.insert(var.clone(), Value::Number(x));
+        result(&mut local, tokens.clone())?.number()
+    }, from, to)
+}
+
+// Evaluate "<expression>, <expression> for <var> in <from>..<to> step <step>" for each value of the variable
+pub fn table_expression(input: &str, memory: &HashMap<String, Value>, csv: bool) -> Result<String, String> {
+    let (expressions, range) = input.split_once(" for ").ok_or("Expected table <expression>, ... for <var> in <from>..<to> step <step>")?;
+    let mut local = memory.clone();
+    let (var, from, to, step) = parse_range(range, &mut local)?;
+    let step = step.unwrap_or(1.0);
+    if step <= 0.0 || step.is_nan() || (to - from) / step >= table::MAX_ROWS as f64 {
+        return Err(format!("Can't make a table from {} to {} with a step of {}", from, to, step))
+    }
+    let expressions = split_arguments(&post_process_paren(tokenize2(expressions)?)?);
+
+    let mut headers = vec![var.clone()];
+    headers.extend(expressions.iter().map(|expression| format_tokens(expression)));
+    let mut rows = Vec::new();
+    let mut i = 0.0;
+    // Multiplying the step rather than adding it avoids accumulating rounding errors
+    while from + i * step <= to {
+        let x = from + i * step;
+        local.insert(var.clone(), Value::Number(x));
+        let mut row = vec![Some(x)];
+        for expression in &expressions {
+            row.push(result(&mut local, expression.clone()).and_then(Value::number).ok().filter(|y| y.is_finite()));
+        }
+        rows.push(row);
+        i += 1.0;
+    }
+    Ok(table::format_table(&headers, &rows, csv))
+}
+
+// "<var> in <from>..<to>" followed by an optional "step <step>", the bounds are evaluated in the memory
+fn parse_range(input: &str, memory: &mut HashMap<String, Value>) -> Result<(String, f64, f64, Option<f64>), String> {
+    let (var, range) = input.split_once(" in ").ok_or("Expected <var> in <from>..<to> after for")?;
+    let (range, step) = match range.split_once(" step ") {
+        Some((range, step)) => (range, Some(result(memory, tokenize2(step)?)?.number()?)),
+        None => (range, None),
+    };
+    let (from, to) = range.split_once("..").ok_or("Expected a range <from>..<to>")?;
+    let var = match &tokenize2(var)?[..] {
+        [Token::Name(var)] => var.clone(),
+        _ => return Err(format!("[{}] is not a variable name", var.trim())),
+    };
+    Ok((var, result(memory, tokenize2(from)?)?.number()?, result(memory, tokenize2(to)?)?.number()?, step))
+}
+
 pub enum LineResult {
     Output(String),
     Nothing
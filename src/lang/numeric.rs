@@ -0,0 +1,262 @@
+/*
+Numbers the operators and functions can compute with, the backend of the session picks one of them.
+A Numeric only needs its conversions and the four operations, the other functions default to the f64 ones
+rounded back to the number: f64, f32, Fixed (6 decimals) and CalculatorDecimal (14 digits like a TI or a Casio).
+Every number of these types is exactly an f64, which is how they are stored in memory.
+The Decimal of arbitrary precision is kept in memory as it is, see decimal.rs
+ */
+
+use std::fmt;
+
+pub trait Numeric: Clone + PartialOrd + fmt::Display {
+    fn from_f64(x: f64) -> Result<Self, String>;
+    fn to_f64(&self) -> f64;
+    fn add(self, other: Self) -> Result<Self, String>;
+    fn subtract(self, other: Self) -> Result<Self, String>;
+    fn multiply(self, other: Self) -> Result<Self, String>;
+    // The divisor isn't zero
+    fn divide(self, other: Self) -> Result<Self, String>;
+
+    fn map(self, f: fn(f64) -> f64) -> Result<Self, String> {
+        Self::from_f64(f(self.to_f64()))
+    }
+    fn is_zero(&self) -> bool {
+        self.to_f64() == 0.0
+    }
+    fn floor(self) -> Result<Self, String> {
+        self.map(f64::floor)
+    }
+    fn power(self, other: Self) -> Result<Self, String> {
+        Self::from_f64(self.to_f64().powf(other.to_f64()))
+    }
+    fn log(self, base: Self) -> Result<Self, String> {
+        Self::from_f64(self.to_f64().log(base.to_f64()))
+    }
+    fn sqrt(self) -> Result<Self, String> {
+        self.map(f64::sqrt)
+    }
+    fn ln(self) -> Result<Self, String> {
+        self.map(f64::ln)
+    }
+    fn exp(self) -> Result<Self, String> {
+        self.map(f64::exp)
+    }
+    fn abs(self) -> Result<Self, String> {
+        self.map(f64::abs)
+    }
+}
+
+impl Numeric for f64 {
+    fn from_f64(x: f64) -> Result<f64, String> {
+        Ok(x)
+    }
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+    fn add(self, other: f64) -> Result<f64, String> {
+        Ok(self + other)
+    }
+    fn subtract(self, other: f64) -> Result<f64, String> {
+        Ok(self - other)
+    }
+    fn multiply(self, other: f64) -> Result<f64, String> {
+        Ok(self * other)
+    }
+    fn divide(self, other: f64) -> Result<f64, String> {
+        Ok(self / other)
+    }
+}
+
+// The operators compute in f32, the other functions are rounded from f64
+impl Numeric for f32 {
+    fn from_f64(x: f64) -> Result<f32, String> {
+        Ok(x as f32)
+    }
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+    fn add(self, other: f32) -> Result<f32, String> {
+        Ok(self + other)
+    }
+    fn subtract(self, other: f32) -> Result<f32, String> {
+        Ok(self - other)
+    }
+    fn multiply(self, other: f32) -> Result<f32, String> {
+        Ok(self * other)
+    }
+    fn divide(self, other: f32) -> Result<f32, String> {
+        Ok(self / other)
+    }
+    fn floor(self) -> Result<f32, String> {
+        Ok(f32::floor(self))
+    }
+    fn power(self, other: f32) -> Result<f32, String> {
+        Ok(self.powf(other))
+    }
+    fn log(self, base: f32) -> Result<f32, String> {
+        Ok(f32::log(self, base))
+    }
+}
+
+const SCALE: i64 = 1_000_000;
+
+// Fixed point number with 6 decimals, 1.5 is Fixed(1500000)
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    fn checked(x: i128) -> Result<Fixed, String> {
+        i64::try_from(x).map(Fixed).map_err(|_| "Overflow of the fixed point arithmetic".to_string())
+    }
+}
+
+// Quotient rounded half away from zero
+fn divide_rounded(a: i128, b: i128) -> i128 {
+    let (quotient, remainder) = (a / b, a % b);
+    if 2 * remainder.abs() >= b.abs() {
+        quotient + a.signum() * b.signum()
+    } else {
+        quotient
+    }
+}
+
+impl Numeric for Fixed {
+    fn from_f64(x: f64) -> Result<Fixed, String> {
+        if x.is_nan() || x.abs() >= (i64::MAX / SCALE) as f64 {
+            return Err(format!("{} is out of the range of the fixed point numbers", x))
+        }
+        Ok(Fixed((x * SCALE as f64).round() as i64))
+    }
+    fn to_f64(&self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+    fn add(self, other: Fixed) -> Result<Fixed, String> {
+        Fixed::checked(self.0 as i128 + other.0 as i128)
+    }
+    fn subtract(self, other: Fixed) -> Result<Fixed, String> {
+        Fixed::checked(self.0 as i128 - other.0 as i128)
+    }
+    fn multiply(self, other: Fixed) -> Result<Fixed, String> {
+        Fixed::checked(divide_rounded(self.0 as i128 * other.0 as i128, SCALE as i128))
+    }
+    fn divide(self, other: Fixed) -> Result<Fixed, String> {
+        Fixed::checked(divide_rounded(self.0 as i128 * SCALE as i128, other.0 as i128))
+    }
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+    fn floor(self) -> Result<Fixed, String> {
+        Ok(Fixed(self.0.div_euclid(SCALE) * SCALE))
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_f64())
+    }
+}
+
+const DIGITS: usize = 14;
+
+// mantissa * 10^exponent, the mantissa has 14 digits unless it's zero.
+// + - * / are exact before being rounded half away from zero, like the BCD registers of a calculator
+#[derive(Clone, Copy)]
+pub struct CalculatorDecimal {
+    mantissa: i128,
+    exponent: i32,
+}
+
+impl CalculatorDecimal {
+    // Rounds an exact mantissa to 14 digits
+    fn round(mantissa: i128, exponent: i32) -> CalculatorDecimal {
+        let length = mantissa.unsigned_abs().to_string().len();
+        if mantissa == 0 || length <= DIGITS {
+            return CalculatorDecimal { mantissa, exponent }
+        }
+        let dropped = (length - DIGITS) as u32;
+        let mut kept = mantissa / 10i128.pow(dropped);
+        let first = (mantissa / 10i128.pow(dropped - 1)).abs() % 10;
+        if first >= 5 {
+            kept += mantissa.signum();
+        }
+        let mut exponent = exponent + dropped as i32;
+        // 99999999999999.5 rounds to a 15th digit
+        if kept.unsigned_abs() == 10u128.pow(DIGITS as u32) {
+            kept /= 10;
+            exponent += 1;
+        }
+        CalculatorDecimal { mantissa: kept, exponent }
+    }
+}
+
+impl Numeric for CalculatorDecimal {
+    fn from_f64(x: f64) -> Result<CalculatorDecimal, String> {
+        if !x.is_finite() {
+            return Err(format!("{} has no decimal value", x))
+        }
+        if x == 0.0 {
+            return Ok(CalculatorDecimal { mantissa: 0, exponent: 0 })
+        }
+        // "-1.2345678901235e-7", already rounded to 14 digits
+        let text = format!("{:.*e}", DIGITS - 1, x);
+        let (digits, exponent) = text.split_once('e').unwrap();
+        let mantissa = digits.replace('.', "").parse::<i128>().unwrap();
+        let exponent = exponent.parse::<i32>().unwrap() - (DIGITS as i32 - 1);
+        Ok(CalculatorDecimal { mantissa, exponent })
+    }
+
+    fn to_f64(&self) -> f64 {
+        format!("{}e{}", self.mantissa, self.exponent).parse::<f64>().unwrap()
+    }
+
+    fn add(self, other: CalculatorDecimal) -> Result<CalculatorDecimal, String> {
+        if other.mantissa == 0 {
+            return Ok(self)
+        }
+        if self.mantissa == 0 {
+            return Ok(other)
+        }
+        let (high, low) = if self.exponent >= other.exponent { (self, other) } else { (other, self) };
+        // Below a tenth of the last digit, the smaller operand can't change the rounding
+        if high.exponent - low.exponent > DIGITS as i32 + 2 {
+            return Ok(high)
+        }
+        let shift = (high.exponent - low.exponent) as u32;
+        Ok(CalculatorDecimal::round(high.mantissa * 10i128.pow(shift) + low.mantissa, low.exponent))
+    }
+
+    fn subtract(self, other: CalculatorDecimal) -> Result<CalculatorDecimal, String> {
+        self.add(CalculatorDecimal { mantissa: -other.mantissa, exponent: other.exponent })
+    }
+
+    fn multiply(self, other: CalculatorDecimal) -> Result<CalculatorDecimal, String> {
+        Ok(CalculatorDecimal::round(self.mantissa * other.mantissa, self.exponent + other.exponent))
+    }
+
+    // The 22 extra digits of the quotient are enough to round it, a truncated remainder only changes the last ones
+    fn divide(self, other: CalculatorDecimal) -> Result<CalculatorDecimal, String> {
+        Ok(CalculatorDecimal::round(self.mantissa * 10i128.pow(22) / other.mantissa, self.exponent - other.exponent - 22))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+}
+
+impl PartialEq for CalculatorDecimal {
+    fn eq(&self, other: &CalculatorDecimal) -> bool {
+        self.to_f64() == other.to_f64()
+    }
+}
+
+impl PartialOrd for CalculatorDecimal {
+    fn partial_cmp(&self, other: &CalculatorDecimal) -> Option<std::cmp::Ordering> {
+        self.to_f64().partial_cmp(&other.to_f64())
+    }
+}
+
+impl fmt::Display for CalculatorDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_f64())
+    }
+}
@@ -0,0 +1,124 @@
+/*
+Character-cell plotting: the expression is sampled once per column, each value is drawn
+on the closest row, the axes are drawn where x = 0 and y = 0 are inside the graph.
+Points that can't be computed (division by zero, ln of a negative number, ...) are left as gaps
+ */
+
+// Odd so that the middle of a symmetric range like -PI..PI is sampled exactly
+const WIDTH: usize = 65;
+const HEIGHT: usize = 20;
+
+pub fn plot<F: FnMut(f64) -> Result<f64, String>>(mut f: F, from: f64, to: f64) -> Result<String, String> {
+    if from >= to || from.is_nan() || to.is_nan() {
+        return Err(format!("Empty plot range: {}..{}", from, to))
+    }
+    let xs: Vec<f64> = (0..WIDTH).map(|column| from + (to - from) * column as f64 / (WIDTH - 1) as f64).collect();
+    let ys: Vec<Option<f64>> = xs.iter().map(|x| f(*x).ok().filter(|y| y.is_finite())).collect();
+
+    let (mut bottom, mut top) = ys.iter().flatten().fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), y| (low.min(*y), high.max(*y)));
+    if bottom > top {
+        return Err(format!("Nothing to plot from {} to {}", from, to))
+    }
+    if bottom == top {
+        // Constant function, center it
+        bottom -= 1.0;
+        top += 1.0;
+    }
+    let row_of = |y: f64| ((top - y) / (top - bottom) * (HEIGHT - 1) as f64).round() as usize;
+
+    let mut grid = vec![vec![' '; WIDTH]; HEIGHT];
+    if bottom <= 0.0 && 0.0 <= top {
+        grid[row_of(0.0)] = vec!['─'; WIDTH];
+    }
+    if let Some(column) = xs.iter().position(|x| *x >= 0.0).filter(|_| from <= 0.0 && 0.0 <= to) {
+        for line in grid.iter_mut() {
+            line[column] = if line[column] == '─' { '┼' } else { '│' };
+        }
+    }
+    for (column, y) in ys.iter().enumerate() {
+        if let Some(y) = y {
+            grid[row_of(*y)][column] = '•';
+        }
+    }
+
+    let (top_label, bottom_label) = (label(top), label(bottom));
+    let margin = top_label.len().max(bottom_label.len());
+    let mut result = String::new();
+    for (row, line) in grid.iter().enumerate() {
+        let text = match row {
+            0 => &top_label,
+            row if row == HEIGHT - 1 => &bottom_label,
+            _ => "",
+        };
+        result.push_str(&format!("{:>margin$} ┤{}\n", text, line.iter().collect::<String>()));
+    }
+    let (from_label, to_label) = (label(from), label(to));
+    result.push_str(&format!("{:>margin$} └{}\n", "", "─".repeat(WIDTH)));
+    result.push_str(&format!("{:>margin$}  {}{:>width$}", "", from_label, to_label, width = WIDTH - from_label.len()));
+    Ok(result)
+}
+
+// Short label for the axes: 3 decimals at most, without trailing zeros
+fn label(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rows of the graph without the labels and the frame
+    fn grid(graph: &str) -> Vec<Vec<char>> {
+        graph.lines().take(HEIGHT).map(|line| line.split_once('┤').unwrap().1.chars().collect()).collect()
+    }
+
+    #[test]
+    fn draws_a_line_with_the_axes() {
+        let graph = plot(Ok, -1.0, 1.0).unwrap();
+        let lines: Vec<&str> = graph.lines().collect();
+        assert_eq!(lines.len(), HEIGHT + 2);
+        // The points above 0.95 are on the top row
+        assert_eq!(lines[0], format!(" 1 ┤{}│{}••", " ".repeat(WIDTH / 2), " ".repeat(WIDTH / 2 - 2)));
+        assert!(lines[HEIGHT - 1].starts_with("-1 ┤•"));
+        assert_eq!(lines[HEIGHT + 1], format!("    -1{:>width$}", "1", width = WIDTH - 2));
+        let grid = grid(&graph);
+        // The axes cross in the middle, where the line goes through 0
+        assert_eq!(grid[0][WIDTH / 2], '│');
+        assert_eq!(grid[HEIGHT / 2][0], '─');
+        assert_eq!(grid.iter().flatten().filter(|c| **c == '•').count(), WIDTH);
+    }
+
+    #[test]
+    fn leaves_gaps_where_the_function_fails() {
+        let graph = plot(|x| if x < 0.0 { Err("ln of a negative number".into()) } else { Ok(x) }, -1.0, 1.0).unwrap();
+        let grid = grid(&graph);
+        for column in 0..WIDTH / 2 {
+            assert!(grid.iter().all(|row| row[column] != '•'));
+        }
+        assert_eq!(grid.iter().flatten().filter(|c| **c == '•').count(), WIDTH / 2 + 1);
+    }
+
+    #[test]
+    fn centers_a_constant_function() {
+        let graph = plot(|_| Ok(3.0), 1.0, 2.0).unwrap();
+        assert!(graph.starts_with("4 ┤ "));
+        assert!(graph.lines().nth(HEIGHT - 1).unwrap().starts_with("2 ┤ "));
+        assert!(grid(&graph)[HEIGHT / 2].iter().all(|c| *c == '•'));
+    }
+
+    #[test]
+    fn rejects_the_empty_plots() {
+        assert_eq!(plot(Ok, 1.0, 1.0).unwrap_err(), "Empty plot range: 1..1");
+        assert_eq!(plot(|_| Err("Division by zero".into()), 0.0, 1.0).unwrap_err(), "Nothing to plot from 0 to 1");
+    }
+
+    #[test]
+    fn labels_with_three_decimals() {
+        assert_eq!(label(1.23456), "1.235");
+        assert_eq!(label(2.5), "2.5");
+        assert_eq!(label(-0.0001), "0");
+        assert_eq!(label(-4.0), "-4");
+    }
+}
@@ -0,0 +1,105 @@
+/*
+Pseudo random numbers of rand(), randint(a, b) and randn() from a SplitMix64 generator.
+It is seeded from the clock unless a seed is given with $seed or --seed, the same seed always gives the same numbers
+ */
+
+use std::cell::Cell;
+use std::f64::consts::PI;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+thread_local! {
+    static STATE: Cell<u64> = Cell::new(clock_seed());
+}
+
+pub fn seed(seed: u64) {
+    STATE.with(|state| state.set(seed));
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64)
+}
+
+fn next() -> u64 {
+    let state = STATE.with(|state| {
+        let next = state.get().wrapping_add(0x9E3779B97F4A7C15);
+        state.set(next);
+        next
+    });
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+// Uniform in [0, 1)
+pub fn uniform() -> f64 {
+    // The 53 high bits fill the mantissa
+    (next() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Uniform integer in [a, b]
+pub fn integer(a: f64, b: f64) -> Result<f64, String> {
+    if a.fract() != 0.0 || b.fract() != 0.0 || a > b {
+        return Err(format!("randint needs two integers a <= b, got {} and {}", a, b))
+    }
+    Ok(a + (uniform() * (b - a + 1.0)).floor())
+}
+
+// Standard normal distribution with the Box-Muller transform
+pub fn normal() -> f64 {
+    let u1 = 1.0 - uniform();
+    let u2 = uniform();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+    use crate::lang::run_line;
+
+    // The numbers drawn by a line after $seed <n>
+    fn draws(n: u64, line: &str) -> Vec<f64> {
+        seed(n);
+        let mut memory = HashMap::new();
+        (0..20).map(|_| {
+            run_line(format!("r = {}", line).as_str(), &mut memory).unwrap();
+            memory["r"].clone().number().unwrap()
+        }).collect()
+    }
+
+    #[test]
+    fn repeats_the_numbers_of_a_seed() {
+        for line in ["rand()", "randint(1, 6)", "randn()"] {
+            assert_eq!(draws(42, line), draws(42, line));
+            assert_ne!(draws(42, line), draws(43, line));
+        }
+    }
+
+    #[test]
+    fn draws_in_the_range() {
+        assert!(draws(1, "rand()").iter().all(|x| (0.0..1.0).contains(x)));
+        let dice = draws(1, "randint(1, 6)");
+        assert!(dice.iter().all(|x| x.fract() == 0.0 && (1.0..=6.0).contains(x)));
+        assert!(draws(1, "randint(-3, -3)").iter().all(|x| *x == -3.0));
+        assert!(draws(1, "randn()").iter().all(|x| x.is_finite()));
+    }
+
+    #[test]
+    fn draws_every_integer_of_the_range() {
+        seed(7);
+        let mut seen = [false; 4];
+        for _ in 0..200 {
+            seen[integer(0.0, 3.0).unwrap() as usize] = true;
+        }
+        assert_eq!(seen, [true; 4]);
+    }
+
+    #[test]
+    fn rejects_the_invalid_ranges() {
+        assert_eq!(integer(3.0, 1.0), Err("randint needs two integers a <= b, got 3 and 1".to_string()));
+        assert!(integer(0.5, 2.0).is_err());
+        assert!(integer(0.0, 2.5).is_err());
+        assert!(run_line("r = randint(2, 1)", &mut HashMap::new()).is_err());
+    }
+}
@@ -1,11 +1,49 @@
 use std::collections::HashMap;
-use crate::lang::calculator::calculate;
-use crate::lang::line_type::{construct_line_type, LineType};
+use crate::lang::calculator::{calculate, post_process_paren, split_arguments};
+use crate::lang::integral::integrate;
+use crate::lang::line_type::{construct_line_type, Condition, LineType};
 use crate::lang::LineResult;
-use crate::lang::tokenizer::{Operation, Token};
+use crate::lang::solver::solve;
+use crate::lang::tokenizer::{Function, Token};
 use crate::lang::tokenizer::Token::{Number, Paren};
+use crate::lang::numeric::Numeric;
+use crate::lang::value::Value;
 
-pub fn run(memory: &mut HashMap<String, f64>, line_type: LineType) -> Result<LineResult, String> {
+// Limit of terms of sum(...) and prod(...)
+const MAX_TERMS: f64 = 1e7;
+
+// Follows the lines of the if and while bodies, the debugger pauses there
+pub trait Observer {
+    // Before the second and next iterations of a while, false stops the loop
+    fn iteration(&mut self, _iteration: usize, _memory: &mut HashMap<String, Value>) -> bool {
+        true
+    }
+    // Before the action of a body, false stops the body
+    fn action(&mut self, _index: usize, _memory: &mut HashMap<String, Value>) -> bool {
+        true
+    }
+    fn report(&mut self, result: Result<LineResult, String>);
+}
+
+// Prints the outputs and the errors of the bodies, an error only skips its own action
+struct Printer;
+
+impl Observer for Printer {
+    fn report(&mut self, result: Result<LineResult, String>) {
+        if let Ok(LineResult::Output(res)) = result {
+            println!("{}", res)
+        } else if let Err(err) = result {
+            println!("PROGRAM ERROR:\n{}", err);
+            println!("Instruction skipped");
+        }
+    }
+}
+
+pub fn run(memory: &mut HashMap<String, Value>, line_type: LineType) -> Result<LineResult, String> {
+    run_with(memory, line_type, &mut Printer)
+}
+
+pub fn run_with<O: Observer>(memory: &mut HashMap<String, Value>, line_type: LineType, observer: &mut O) -> Result<LineResult, String> {
     match line_type {
         LineType::Nothing => {
 
@@ -13,108 +51,251 @@ pub fn run(memory: &mut HashMap<String, f64>, line_type: LineType) -> Result<Lin
         LineType::Out(out) => {
             let mut result = "".to_string();
             for string in out {
-                result.push_str(":");
-                let num = get_from_mem(memory, string)?;
-                result.push_str(&*format!("{}", num));
+                result.push(':');
+                let value = get_from_mem(memory, string)?;
+                // A matrix takes several lines, it starts on its own line to stay aligned
+                if let Value::Matrix(_) = value {
+                    result.push('\n');
+                }
+                result.push_str(&format!("{}", value));
             }
             return Ok(LineResult::Output(result))
         }
         LineType::VarOperate(var, op, ex) => {
             let num = result(memory,ex)?;
-            match op {
-                Operation::Assign => {
+            match op.operator() {
+                None => {
                     memory.insert(var, num);
                 }
-                Operation::AddVar => {
-                    let ancient = get_from_mem(memory, var.clone())?;
-                    memory.insert(var, ancient + num);
-                }
-                Operation::SubtractVar => {
+                Some(operator) => {
                     let ancient = get_from_mem(memory, var.clone())?;
-                    memory.insert(var, ancient - num);
-                }
-                Operation::MultiplyVar => {
-                    let ancient = get_from_mem(memory, var.clone())?;
-                    memory.insert(var, ancient * num);
-                }
-                Operation::DivideVar => {
-                    let ancient = get_from_mem(memory, var.clone())?;
-                    memory.insert(var, ancient / num);
+                    memory.insert(var, ancient.operate(&operator, num)?);
                 }
             }
         }
-        LineType::If(a, c, b, actions) => {
-            let a = result(memory, a)?;
-            let b = result(memory, b)?;
-            if c.compare(a, b) {
-                for action in actions {
-                    let lt = construct_line_type(action)?;
-                    let result = run(memory, lt);
-                    if let Ok(LineResult::Output(res)) = result {
-                        println!("{}", res)
-                    } else if let Err(err) = result {
-                        println!("PROGRAM ERROR:\n{}", err);
-                        println!("Instruction skipped");
-                    }
-                }
+        LineType::If(condition, actions) => {
+            if check(memory, &condition)? {
+                run_body(memory, &actions, observer)?;
             }
             return Ok(LineResult::Nothing);
         }
-        LineType::While(ref a, ref c, ref b, ref actions) => {
-            let mut line_types = Vec::new();
-            for action in actions {
-                line_types.push(construct_line_type(action.clone())?)
-            }
-            while c.compare(result(memory, a.clone())?, result(memory, b.clone())?) == true {
-                for lt in &line_types {
-                    //let lt = construct_line_type(action.clone())?;
-                    let result = run(memory, lt.clone());
-                    if let Ok(LineResult::Output(res)) = result {
-                        println!("{}", res)
-                    } else if let Err(err) = result {
-                        println!("PROGRAM ERROR:\n{}", err);
-                        println!("Instruction skipped");
-                    }
+        LineType::While(condition, actions) => {
+            let mut iteration = 0;
+            while check(memory, &condition)? {
+                iteration += 1;
+                if iteration > 1 && !observer.iteration(iteration, memory) {
+                    break;
+                }
+                if !run_body(memory, &actions, observer)? {
+                    break;
                 }
             }
-
             return Ok(LineResult::Nothing);
         }
+        LineType::Solve(var, a, b) => {
+            let root = solve(memory, &var, a, b)?;
+            memory.insert(var, Value::Number(root));
+            return Ok(LineResult::Output(format!(":{}", root)))
+        }
     }
     Ok(LineResult::Nothing)
 }
 
-fn replace_var(memory: &mut HashMap<String, f64>, tokens : Vec<Token>) -> Result<Vec<Token>, String> {
+// False when the observer stopped the body
+fn run_body<O: Observer>(memory: &mut HashMap<String, Value>, actions: &[Vec<Token>], observer: &mut O) -> Result<bool, String> {
+    let mut line_types = Vec::new();
+    for action in actions {
+        line_types.push(construct_line_type(action.clone())?)
+    }
+    for (index, line_type) in line_types.into_iter().enumerate() {
+        if !observer.action(index, memory) {
+            return Ok(false);
+        }
+        let result = run_with(memory, line_type, observer);
+        observer.report(result);
+    }
+    Ok(true)
+}
+
+fn replace_var(memory: &mut HashMap<String, Value>, tokens : Vec<Token>) -> Result<Vec<Token>, String> {
     let mut tokens = tokens;
-    for i in 0..tokens.len() {
+    let mut i = 0;
+    while i < tokens.len() {
         if let Token::Name(name) = tokens.get(i).unwrap() {
-            if let Some(num) = memory.get(name) {
-                tokens[i] = Number(*num)
-            } else if let None = memory.get(name) {
+            if let Some(value) = memory.get(name) {
+                tokens[i] = value.clone().token()
+            } else {
                 return Err(format!("Var [{}] doesn't exist", name))
-            } {
-
             }
         } else if let Paren(toks) = tokens.get(i).unwrap() {
             tokens[i] = Paren(replace_var(memory, toks.clone())?)
+        } else if let Token::Bracket(toks) = tokens.get(i).unwrap() {
+            tokens[i] = list(memory, toks)?.token()
+        } else if let (Token::Function(function), Some(args)) = (tokens.get(i).unwrap(), tokens.get(i + 1)) {
+            // The bound variable only exists while the function is computed, sum(l) of a list is a plain call
+            let args = split_arguments(std::slice::from_ref(args));
+            if function.binds_variable() && args.len() != 1 {
+                let value = bound_function(memory, function, args)?;
+                tokens[i] = Number(value);
+                tokens.remove(i + 1);
+            }
         }
+        // "l[0]", "[1, 2, 3][i]", "m[0][1]"
+        while let Some(Token::Bracket(index)) = tokens.get(i + 1) {
+            let value = match &tokens[i] {
+                Token::List(list) => Value::List(list.clone()),
+                Token::Matrix(matrix) => Value::Matrix(matrix.clone()),
+                Number(num) => Value::Number(*num),
+                Token::Decimal(num) => Value::Decimal(num.clone()),
+                Token::Integer(num) => Value::Integer(num.clone()),
+                _ => break,
+            };
+            let index = result(memory, index.clone())?.number()?;
+            tokens[i] = value.index(index)?.token();
+            tokens.remove(i + 1);
+        }
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+// Value of a literal, "[1, a, 2 + b]" is a list and "[[1, 2], [3, 4]]" a matrix of 2 rows
+fn list(memory: &mut HashMap<String, Value>, tokens: &[Token]) -> Result<Value, String> {
+    let mut numbers = Vec::new();
+    let mut rows = Vec::new();
+    for element in split_arguments(tokens) {
+        match result(memory, element)? {
+            Value::Number(num) => numbers.push(num),
+            Value::Decimal(num) => numbers.push(num.to_f64()),
+            Value::Integer(num) => numbers.push(num.to_f64()),
+            Value::List(row) => rows.push(row),
+            Value::Matrix(_) => return Err("A matrix can't hold matrices".into()),
+        }
+    }
+    if rows.is_empty() {
+        return Ok(Value::List(numbers))
+    }
+    if !numbers.is_empty() {
+        return Err("A list can't hold both numbers and lists".into())
+    }
+    if rows.iter().any(|row| row.is_empty() || row.len() != rows[0].len()) {
+        return Err("The rows of a matrix must have the same length".into())
     }
-    return Ok(tokens);
+    Ok(Value::Matrix(rows))
 }
 
-fn result(memory: &mut HashMap<String, f64>, tokens : Vec<Token>) -> Result<f64, String> {
-    let rep = replace_var(memory,tokens)?;
+fn bound_function(memory: &HashMap<String, Value>, function: &Function, args: Vec<Vec<Token>>) -> Result<f64, String> {
+    let (expression, var, from, to) = match &args[..] {
+        [expression, var, from, to] => match &var[..] {
+            [Token::Name(var)] => (expression, var, from, to),
+            _ => return Err(format!("{}: the second argument must be a variable name", function)),
+        },
+        _ => return Err(format!("{} takes 4 arguments (expression, variable, from, to), {} given", function, args.len())),
+    };
+    // Evaluated in a copy of the memory so the variable doesn't leak
+    let mut local = memory.clone();
+    let from = result(&mut local, from.clone())?.number()?;
+    let to = result(&mut local, to.clone())?.number()?;
+    let mut f = |x: f64| {
+        local.insert(var.clone(), Value::Number(x));
+        result(&mut local, expression.clone())?.number()
+    };
+    match function {
+        Function::Integrate => integrate(f, from, to),
+        Function::Sum | Function::Prod => {
+            if to - from > MAX_TERMS {
+                return Err(format!("{}: too many terms from {} to {}", function, from, to))
+            }
+            let mut total = if *function == Function::Sum { 0.0 } else { 1.0 };
+            let mut k = from;
+            while k <= to {
+                if *function == Function::Sum {
+                    total += f(k)?;
+                } else {
+                    total *= f(k)?;
+                }
+                k += 1.0;
+            }
+            Ok(total)
+        }
+        _ => Err(format!("{} doesn't bind a variable", function)),
+    }
+}
+
+pub fn result(memory: &mut HashMap<String, Value>, tokens : Vec<Token>) -> Result<Value, String> {
+    let rep = replace_var(memory, post_process_paren(tokens)?)?;
     let cal = calculate(rep)?;
-    return Ok(cal);
+    Ok(cal)
 }
 
-pub fn get_from_mem(memory: &mut HashMap<String, f64>, string: String) -> Result<f64, String> {
-    return match memory.get(&string) {
+// Every comparison of the chain must hold, the evaluation stops at the first one that doesn't
+pub fn check(memory: &mut HashMap<String, Value>, condition: &Condition) -> Result<bool, String> {
+    let mut a = result(memory, condition.first.clone())?;
+    for (c, operand) in &condition.rest {
+        let b = result(memory, operand.clone())?;
+        if !a.compare(c, &b)? {
+            return Ok(false);
+        }
+        a = b;
+    }
+    Ok(true)
+}
+
+pub fn get_from_mem(memory: &mut HashMap<String, Value>, string: String) -> Result<Value, String> {
+    match memory.get(&string) {
         None => {
             Err(format!("Var [{}] not found in memory", string))
         }
-        Some(num) => {
-            Ok(*num)
+        Some(value) => {
+            Ok(value.clone())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::run_line;
+
+    // Whether "if <condition>" runs its action
+    fn holds(condition: &str) -> bool {
+        let mut memory = HashMap::from([("x".to_string(), Value::Number(2.0))]);
+        run_line(format!("if {}: r = 1", condition).as_str(), &mut memory).unwrap();
+        memory.contains_key("r")
+    }
+
+    #[test]
+    fn chains_the_comparisons() {
+        assert!(holds("1 < 2 < 3"));
+        assert!(holds("3 > 2 > 1.5"));
+        assert!(holds("1 <= x <= 2"));
+        assert!(!holds("1 < 3 < 2"));
+        assert!(!holds("3 > 1 > 2"));
+        assert!(!holds("2 < 1 < 3"));
+    }
+
+    #[test]
+    fn compares_the_inequality() {
+        assert!(holds("1 != 2"));
+        assert!(holds("1 <> 2"));
+        assert!(!holds("x != 2"));
+        assert!(!holds("x <> 1 + 1"));
+        assert!(holds("1 < x != 3"));
+    }
+
+    #[test]
+    fn stops_at_the_first_false_comparison() {
+        // The division by zero is never evaluated
+        assert!(!holds("2 < 1 < 1 / 0"));
+        let mut memory = HashMap::new();
+        assert!(run_line("if 1 < 2 < 1 / 0: r = 1", &mut memory).is_err());
+    }
+
+    #[test]
+    fn loops_while_the_chain_holds() {
+        let mut memory = HashMap::new();
+        run_line("i = 0", &mut memory).unwrap();
+        run_line("while 0 <= i < 5: i = i + 1", &mut memory).unwrap();
+        assert_eq!(memory["i"].clone().number(), Ok(5.0));
+    }
+}
@@ -0,0 +1,148 @@
+/*
+The simplifier rewrites an expression three into a shorter equivalent one:
+constant branches are computed ("2*PI*3"), identity operations are removed ("x*1", "x+0")
+and constants are grouped together in chains of additions or multiplications ("2*x*3" => "6 * x")
+ */
+
+use crate::lang::calculator::{Expression, Possible};
+use crate::lang::calculator::Possible::{PossCall, PossExpression, PossToken};
+use crate::lang::tokenizer::{Operator, Token};
+use crate::lang::tokenizer::Token::Number;
+use crate::lang::value::Value;
+
+impl Possible {
+    pub fn simplify(self) -> Possible {
+        match self {
+            PossToken(token) => PossToken(token),
+            PossCall(function, args) => {
+                let args: Vec<Possible> = args.into_iter().map(|arg| arg.simplify()).collect();
+                let values: Vec<Value> = args.iter().filter_map(|arg| match arg {
+                    PossToken(Number(num)) => Some(Value::Number(*num)),
+                    _ => None,
+                }).collect();
+                if values.len() == args.len() && !function.is_random() {
+                    if let Ok(Value::Number(result)) = function.apply(values) {
+                        if result.is_finite() {
+                            return number(result)
+                        }
+                    }
+                }
+                PossCall(function, args)
+            }
+            PossExpression(expression) => {
+                let Expression { a, o, b } = *expression;
+                simplify_node(a.simplify(), o, b.simplify())
+            }
+        }
+    }
+}
+
+fn simplify_node(a: Possible, o: Operator, b: Possible) -> Possible {
+    match (a, o, b) {
+        // Constant folding, errors like a division by zero are left for the evaluation to report
+        (PossToken(Number(a)), o, PossToken(Number(b))) => {
+            match o.operate(a, b) {
+                Ok(result) if result.is_finite() => number(result),
+                _ => node(number(a), o, number(b)),
+            }
+        }
+        // Identity operations
+        (x, Operator::Plus, PossToken(Number(0.0))) | (PossToken(Number(0.0)), Operator::Plus, x) => x,
+        (x, Operator::Minus, PossToken(Number(0.0))) => x,
+        (PossToken(Number(0.0)), Operator::Minus, x) => simplify_node(number(-1.0), Operator::Multiply, x),
+        (x, Operator::Multiply, PossToken(Number(1.0))) | (PossToken(Number(1.0)), Operator::Multiply, x) => x,
+        // Dropping an operand is only safe when it can't fail: "0 * (1 / 0)" stays for the evaluation to report
+        (x, Operator::Multiply, PossToken(Number(0.0))) | (PossToken(Number(0.0)), Operator::Multiply, x) if is_leaf(&x) => number(0.0),
+        (x, Operator::Divide, PossToken(Number(1.0))) => x,
+        (x, Operator::Exponent, PossToken(Number(1.0))) => x,
+        (x, Operator::Exponent, PossToken(Number(0.0))) if is_leaf(&x) => number(1.0),
+        (PossToken(Number(1.0)), Operator::Exponent, x) if is_leaf(&x) => number(1.0),
+        // Constants are written first in multiplications: "x * 2" => "2 * x"
+        (x @ (PossExpression(_) | PossCall(_, _) | PossToken(Token::Name(_))), Operator::Multiply, PossToken(Number(c))) => {
+            simplify_node(number(c), Operator::Multiply, x)
+        }
+        // Group the constants of "c1 * (c2 * x)" and "(c1 * x) / c2"
+        (PossToken(Number(c1)), Operator::Multiply, PossExpression(inner)) if inner.o == Operator::Multiply && matches!(*inner.a, PossToken(Number(_))) => {
+            let Expression { a, o: _, b } = *inner;
+            simplify_node(simplify_node(number(c1), Operator::Multiply, *a), Operator::Multiply, *b)
+        }
+        (PossExpression(inner), Operator::Divide, PossToken(Number(c2))) if inner.o == Operator::Multiply && matches!(*inner.a, PossToken(Number(_))) => {
+            let Expression { a, o: _, b } = *inner;
+            simplify_node(simplify_node(*a, Operator::Divide, number(c2)), Operator::Multiply, *b)
+        }
+        // Group the constants of "(c1 + x) + c2" and "(x + c1) + c2"
+        (PossExpression(inner), Operator::Plus, PossToken(Number(c2))) if inner.o == Operator::Plus => {
+            let Expression { a, o: _, b } = *inner;
+            match (*a, *b) {
+                (PossToken(Number(c1)), x) | (x, PossToken(Number(c1))) => {
+                    simplify_node(simplify_node(number(c1), Operator::Plus, number(c2)), Operator::Plus, x)
+                }
+                (a, b) => node(node(a, Operator::Plus, b), Operator::Plus, number(c2)),
+            }
+        }
+        (a, o, b) => node(a, o, b),
+    }
+}
+
+// A number or a variable
+fn is_leaf(x: &Possible) -> bool {
+    matches!(x, PossToken(Number(_) | Token::Name(_)))
+}
+
+fn node(a: Possible, o: Operator, b: Possible) -> Possible {
+    Possible::expression(Expression::new(a, o, b))
+}
+
+fn number(num: f64) -> Possible {
+    PossToken(Number(num))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::calculator::parse_expression;
+    use crate::lang::tokenizer::tokenize2;
+
+    fn simplify(input: &str) -> String {
+        parse_expression(tokenize2(input).unwrap()).unwrap().simplify().to_string()
+    }
+
+    #[test]
+    fn folds_constants() {
+        assert_eq!(simplify("2 * 3 + 4"), "10");
+        assert_eq!(simplify("x + 2 * 3"), "x + 6");
+        assert_eq!(simplify("sqrt(16) * x"), "4 * x");
+    }
+
+    #[test]
+    fn removes_identity_operations() {
+        assert_eq!(simplify("x * 1 + 0"), "x");
+        assert_eq!(simplify("x ^ 1 - 0"), "x");
+        assert_eq!(simplify("0 * x + y"), "y");
+        assert_eq!(simplify("x ^ 0"), "1");
+        assert_eq!(simplify("x / 1"), "x");
+    }
+
+    #[test]
+    fn groups_constants() {
+        assert_eq!(simplify("2 * x * 3"), "6 * x");
+        assert_eq!(simplify("x * 2"), "2 * x");
+        assert_eq!(simplify("(2 * x) / 4"), "0.5 * x");
+        assert_eq!(simplify("(1 + x) + 2"), "3 + x");
+    }
+
+    #[test]
+    fn leaves_errors_for_the_evaluation() {
+        assert_eq!(simplify("x + 1 / 0"), "x + 1 / 0");
+        assert_eq!(simplify("rand() + 1"), "rand() + 1");
+    }
+
+    #[test]
+    fn keeps_the_operands_that_can_fail() {
+        assert_eq!(simplify("0 * (1 / 0)"), "0 * (1 / 0)");
+        assert_eq!(simplify("(1 / 0) * 0"), "0 * (1 / 0)");
+        assert_eq!(simplify("(1 / 0) ^ 0"), "(1 / 0) ^ 0");
+        assert_eq!(simplify("1 ^ sqrt(x)"), "1 ^ sqrt(x)");
+        assert_eq!(simplify("0 * 5 + 2 * 0"), "0");
+        assert_eq!(simplify("1 ^ x"), "1");
+    }
+}
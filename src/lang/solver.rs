@@ -0,0 +1,202 @@
+/*
+The solver finds a root of "a == b" for one variable by looking for a zero of f(x) = a - b:
+sign changes of f are searched around the current value of the variable, then refined with Newton steps
+(secant steps when the derivative can't be computed) kept inside the bracket by bisection.
+f is evaluated like any line of the script, so sum(...) and integrate(...) bind their variable as usual
+ */
+
+use std::collections::HashMap;
+use crate::lang::calculator::{parse_expression, Expression, Possible};
+use crate::lang::runner::result;
+use crate::lang::tokenizer::{Operator, Token};
+use crate::lang::value::Value;
+
+const MAX_ITERATIONS: usize = 200;
+const EXPANSIONS: usize = 80;
+// The equation is considered solved below that difference between both sides
+const TOLERANCE: f64 = 1e-9;
+
+pub fn solve(memory: &HashMap<String, Value>, var: &str, a: Vec<Token>, b: Vec<Token>) -> Result<f64, String> {
+    let f = Possible::expression(Expression::new(parse_expression(a.clone())?, Operator::Minus, parse_expression(b.clone())?));
+    let df = f.derive(var).ok().map(|df| df.simplify());
+    let mut equation = Equation { a, b, df, memory: memory.clone(), var };
+    let start = match memory.get(var) {
+        Some(value @ (Value::Number(_) | Value::Integer(_))) => value.clone().number()?,
+        _ => 0.0,
+    };
+
+    if equation.value(start) == Some(0.0) {
+        return Ok(start)
+    }
+    for (low, high) in equation.brackets(start) {
+        if let Some(root) = equation.refine(low, high) {
+            return Ok(root)
+        }
+    }
+    // No sign change (double roots like x^2 == 0), try Newton from the start
+    equation.newton(start).ok_or(format!("No solution found for [{}]", var))
+}
+
+struct Equation<'a> {
+    // Both sides of the equation, f is a - b
+    a: Vec<Token>,
+    b: Vec<Token>,
+    df: Option<Possible>,
+    // Copy of the memory where the variable is replaced, the global memory is only updated with the root
+    memory: HashMap<String, Value>,
+    var: &'a str,
+}
+
+impl Equation<'_> {
+    fn value(&mut self, x: f64) -> Option<f64> {
+        self.memory.insert(self.var.to_string(), Value::Number(x));
+        let a = result(&mut self.memory, self.a.clone()).and_then(Value::number).ok()?;
+        let b = result(&mut self.memory, self.b.clone()).and_then(Value::number).ok()?;
+        Some(a - b).filter(|y| y.is_finite())
+    }
+
+    fn slope(&mut self, x: f64) -> Option<f64> {
+        self.memory.insert(self.var.to_string(), Value::Number(x));
+        let slope = match &self.df {
+            Some(df) => df.clone().substitute(&self.memory).and_then(|three| three.solve()?.number()).ok(),
+            None => {
+                // Secant on a small step
+                let h = 1e-7 * (1.0 + x.abs());
+                Some((self.value(x + h)? - self.value(x)?) / h)
+            }
+        };
+        slope.filter(|slope| slope.is_finite() && *slope != 0.0)
+    }
+
+    fn is_root(&mut self, x: f64) -> bool {
+        matches!(self.value(x), Some(y) if y.abs() < TOLERANCE)
+    }
+
+    // Intervals where f changes sign, closest to the start first, further and further on both sides
+    fn brackets(&mut self, start: f64) -> Vec<(f64, f64)> {
+        let mut brackets = Vec::new();
+        let mut low = (start, self.value(start));
+        let mut high = low;
+        let mut step = 0.1 * start.abs().max(1.0);
+        for _ in 0..EXPANSIONS {
+            for side in [1.0, -1.0] {
+                let previous = if side < 0.0 { &mut low } else { &mut high };
+                let x = start + side * step;
+                let y = self.value(x);
+                if let (Some(previous_y), Some(y)) = (previous.1, y) {
+                    if previous_y.signum() != y.signum() {
+                        brackets.push((previous.0.min(x), previous.0.max(x)));
+                    }
+                }
+                if y.is_some() {
+                    *previous = (x, y);
+                }
+            }
+            step *= 1.6;
+        }
+        brackets
+    }
+
+    fn refine(&mut self, mut low: f64, mut high: f64) -> Option<f64> {
+        let mut f_low = self.value(low)?;
+        let mut x = (low + high) / 2.0;
+        let mut previous_width = f64::INFINITY;
+        for _ in 0..MAX_ITERATIONS {
+            // Newton step, replaced by a bisection when it leaves the bracket or the bracket shrinks too slowly
+            let mut next = match self.slope(x) {
+                Some(slope) => x - self.value(x)? / slope,
+                None => (low + high) / 2.0,
+            };
+            if !(next > low && next < high) || high - low > previous_width / 2.0 {
+                next = (low + high) / 2.0;
+            }
+            previous_width = high - low;
+            let y = self.value(next)?;
+            if y == 0.0 {
+                return Some(next)
+            }
+            if y.signum() == f_low.signum() {
+                low = next;
+                f_low = y;
+            } else {
+                high = next;
+            }
+            let converged = (next - x).abs() <= f64::EPSILON * next.abs().max(1.0) || high - low <= f64::EPSILON * next.abs().max(1.0);
+            x = next;
+            if converged {
+                break;
+            }
+        }
+        // A sign change can also come from a pole like 1/x
+        if self.is_root(x) { Some(x) } else { None }
+    }
+
+    fn newton(&mut self, start: f64) -> Option<f64> {
+        let mut x = start;
+        for _ in 0..MAX_ITERATIONS {
+            let y = self.value(x)?;
+            if y == 0.0 {
+                return Some(x)
+            }
+            let next = x - y / self.slope(x)?;
+            if !next.is_finite() {
+                return None
+            }
+            let converged = (next - x).abs() <= f64::EPSILON * next.abs().max(1.0);
+            x = next;
+            if converged {
+                break;
+            }
+        }
+        if self.is_root(x) { Some(x) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::calculator::post_process_paren;
+    use crate::lang::tokenizer::tokenize2;
+
+    fn solve_with(memory: &HashMap<String, Value>, equation: &str) -> Result<f64, String> {
+        let (a, b) = equation.split_once("==").unwrap();
+        let tokens = |side: &str| post_process_paren(tokenize2(side).unwrap()).unwrap();
+        solve(memory, "x", tokens(a), tokens(b))
+    }
+
+    fn assert_root(equation: &str, expected: f64) {
+        let root = solve_with(&HashMap::new(), equation).unwrap();
+        assert!((root - expected).abs() < 1e-9, "{}: {} instead of {}", equation, root, expected);
+    }
+
+    #[test]
+    fn solves_polynomials() {
+        assert_root("2x + 3 == 7", 2.0);
+        assert_root("x^3 == 8", 2.0);
+        assert_root("x^2 == 0", 0.0);
+    }
+
+    #[test]
+    fn solves_transcendental_equations() {
+        assert_root("cos(x) == x", 0.7390851332151607);
+        assert_root("exp(x) == 10", 10f64.ln());
+    }
+
+    #[test]
+    fn starts_from_the_value_in_memory() {
+        let memory = HashMap::from([("x".to_string(), Value::Number(-5.0))]);
+        let root = solve_with(&memory, "x^2 == 4").unwrap();
+        assert!((root + 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn solves_equations_with_bound_variables() {
+        assert_root("sum(k * x, k, 1, 4) == 5", 0.5);
+        assert_root("integrate(t^2, t, 0, x) == 9", 3.0);
+    }
+
+    #[test]
+    fn reports_equations_without_solution() {
+        assert!(solve_with(&HashMap::new(), "x^2 == -1").is_err());
+    }
+}
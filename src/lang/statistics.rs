@@ -0,0 +1,139 @@
+/*
+Statistics of a list of numbers, the ones of the STAT menu of a scientific calculator.
+stdev and variance are the ones of a sample (divided by n - 1), pstdev and pvariance the ones of a population
+ */
+
+pub fn mean(list: &[f64]) -> Result<f64, String> {
+    if list.is_empty() {
+        return Err("Mean of an empty list".into())
+    }
+    Ok(list.iter().sum::<f64>() / list.len() as f64)
+}
+
+pub fn median(list: &[f64]) -> Result<f64, String> {
+    if list.is_empty() {
+        return Err("Median of an empty list".into())
+    }
+    quantile(list, 0.5)
+}
+
+// Linear interpolation between the two closest ranks, quantile(l, 0.25) is the first quartile
+pub fn quantile(list: &[f64], p: f64) -> Result<f64, String> {
+    if list.is_empty() {
+        return Err("Quantile of an empty list".into())
+    }
+    if !(0.0..=1.0).contains(&p) {
+        return Err(format!("Quantile {} is not between 0 and 1", p))
+    }
+    let sorted = sorted(list)?;
+    let rank = p * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    Ok(sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64))
+}
+
+pub fn variance(list: &[f64], sample: bool) -> Result<f64, String> {
+    let needed = if sample { 2 } else { 1 };
+    if list.len() < needed {
+        return Err(format!("Variance needs at least {} values", needed))
+    }
+    let mean = mean(list)?;
+    Ok(list.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (list.len() + 1 - needed) as f64)
+}
+
+pub fn stdev(list: &[f64], sample: bool) -> Result<f64, String> {
+    Ok(variance(list, sample)?.sqrt())
+}
+
+pub fn min(list: &[f64]) -> Result<f64, String> {
+    sorted(list)?.first().copied().ok_or("Minimum of an empty list".into())
+}
+
+pub fn max(list: &[f64]) -> Result<f64, String> {
+    sorted(list)?.last().copied().ok_or("Maximum of an empty list".into())
+}
+
+// Least squares line y = slope * x + intercept and its coefficient of determination r²
+pub fn linear_regression(xs: &[f64], ys: &[f64]) -> Result<(f64, f64, f64), String> {
+    if xs.len() != ys.len() {
+        return Err(format!("Lists of different lengths: {} and {}", xs.len(), ys.len()))
+    }
+    if xs.len() < 2 {
+        return Err("Linear regression needs at least 2 points".into())
+    }
+    let (mean_x, mean_y) = (mean(xs)?, mean(ys)?);
+    let sxx: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    let syy: f64 = ys.iter().map(|y| (y - mean_y).powi(2)).sum();
+    let sxy: f64 = xs.iter().zip(ys).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    if sxx == 0.0 {
+        return Err("Linear regression of points with the same x".into())
+    }
+    let slope = sxy / sxx;
+    // Every point on a horizontal line is a perfect fit
+    let r2 = if syy == 0.0 { 1.0 } else { sxy * sxy / (sxx * syy) };
+    Ok((slope, mean_y - slope * mean_x, r2))
+}
+
+fn sorted(list: &[f64]) -> Result<Vec<f64>, String> {
+    if list.iter().any(|x| x.is_nan()) {
+        return Err("Statistics of a list containing NaN".into())
+    }
+    let mut sorted = list.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Ok(sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_median() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Ok(2.0));
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Ok(2.5));
+        assert_eq!(median(&[7.0]), Ok(7.0));
+    }
+
+    #[test]
+    fn interpolates_the_quantiles() {
+        let list = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(quantile(&list, 0.0), Ok(1.0));
+        assert_eq!(quantile(&list, 0.25), Ok(2.0));
+        assert_eq!(quantile(&list, 0.1), Ok(1.4));
+        assert_eq!(quantile(&[10.0, 0.0], 0.75), Ok(7.5));
+        assert_eq!(quantile(&list, 1.0), Ok(5.0));
+        assert_eq!(quantile(&list, 1.5), Err("Quantile 1.5 is not between 0 and 1".to_string()));
+    }
+
+    #[test]
+    fn divides_the_variance_of_a_sample_by_n_minus_1() {
+        let list = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(variance(&list, false), Ok(4.0));
+        assert_eq!(stdev(&list, false), Ok(2.0));
+        assert_eq!(variance(&list, true), Ok(32.0 / 7.0));
+        assert_eq!(variance(&[3.0], false), Ok(0.0));
+        assert_eq!(variance(&[3.0], true), Err("Variance needs at least 2 values".to_string()));
+    }
+
+    #[test]
+    fn fits_an_exact_line() {
+        assert_eq!(linear_regression(&[0.0, 1.0, 2.0, 3.0], &[1.0, 3.0, 5.0, 7.0]), Ok((2.0, 1.0, 1.0)));
+        assert_eq!(linear_regression(&[1.0, 2.0, 3.0], &[4.0, 4.0, 4.0]), Ok((0.0, 4.0, 1.0)));
+        let (slope, intercept, r2) = linear_regression(&[1.0, 2.0, 3.0], &[1.0, 3.0, 2.0]).unwrap();
+        assert_eq!((slope, intercept), (0.5, 1.0));
+        assert!((r2 - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rejects_the_lists_too_short() {
+        assert!(mean(&[]).is_err());
+        assert!(median(&[]).is_err());
+        assert!(quantile(&[], 0.5).is_err());
+        assert!(variance(&[], false).is_err());
+        assert!(min(&[]).is_err());
+        assert!(max(&[]).is_err());
+        assert_eq!(linear_regression(&[1.0], &[2.0]), Err("Linear regression needs at least 2 points".to_string()));
+        assert!(linear_regression(&[1.0, 1.0], &[2.0, 3.0]).is_err());
+        assert!(linear_regression(&[1.0, 2.0], &[2.0]).is_err());
+        assert!(median(&[1.0, f64::NAN]).is_err());
+    }
+}
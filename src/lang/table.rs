@@ -0,0 +1,96 @@
+/*
+Table of values, like the TABLE view of graphing calculators: one row per value of the variable,
+one column per expression. Values that can't be computed are written "undef" (empty in CSV)
+ */
+
+// Limit of rows of a table, protects from a tiny step over a large range
+pub const MAX_ROWS: usize = 10_000;
+
+pub fn format_table(headers: &[String], rows: &[Vec<Option<f64>>], csv: bool) -> String {
+    let cells: Vec<Vec<String>> = rows.iter().map(|row| row.iter().map(|value| match value {
+        Some(value) => format!("{}", value),
+        None if csv => String::new(),
+        None => "undef".to_string(),
+    }).collect()).collect();
+
+    if csv {
+        let mut result = headers.iter().map(|header| csv_field(header)).collect::<Vec<String>>().join(",");
+        for row in cells {
+            result.push('\n');
+            result.push_str(&row.join(","));
+        }
+        return result
+    }
+
+    let widths: Vec<usize> = headers.iter().enumerate()
+        .map(|(i, header)| cells.iter().map(|row| row[i].chars().count()).fold(header.chars().count(), usize::max))
+        .collect();
+    let line = |row: &[String]| row.iter().zip(&widths)
+        .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+        .collect::<Vec<String>>().join(" │ ");
+    let mut result = line(headers);
+    result.push('\n');
+    result.push_str(&widths.iter().map(|width| "─".repeat(*width)).collect::<Vec<String>>().join("─┼─"));
+    for row in cells {
+        result.push('\n');
+        result.push_str(&line(&row));
+    }
+    result
+}
+
+// Expressions like "sum(k, k, 1, x)" contain commas and must be quoted
+fn csv_field(text: &str) -> String {
+    if text.contains(',') || text.contains('"') {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+    use crate::lang::table_expression;
+
+    #[test]
+    fn tabulates_the_expressions() {
+        let table = table_expression("x^2, 1/x for x in -1..1 step 0.5", &HashMap::new(), false).unwrap();
+        assert_eq!(table, [
+            "   x │ x ^ 2 │ 1 / x",
+            "─────┼───────┼──────",
+            "  -1 │     1 │    -1",
+            "-0.5 │  0.25 │    -2",
+            "   0 │     0 │ undef",
+            " 0.5 │  0.25 │     2",
+            "   1 │     1 │     1",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn writes_csv() {
+        let table = table_expression("sum(k, k, 1, n), ln(n) for n in 0..2", &HashMap::new(), true).unwrap();
+        assert_eq!(table, "n,\"sum(k, k, 1, n)\",ln(n)\n0,0,\n1,1,0\n2,3,0.6931471805599453");
+    }
+
+    #[test]
+    fn reads_the_bounds_in_the_memory() {
+        let memory = HashMap::from([("a".to_string(), crate::lang::value::Value::Number(2.0))]);
+        let table = table_expression("a * x for x in 0..a", &memory, true).unwrap();
+        assert_eq!(table, "x,a * x\n0,0\n1,2\n2,4");
+    }
+
+    #[test]
+    fn rejects_the_steps_that_never_end() {
+        assert!(table_expression("x for x in 0..1 step 0", &HashMap::new(), false).is_err());
+        assert!(table_expression("x for x in 0..1 step -1", &HashMap::new(), false).is_err());
+        assert!(table_expression("x for x in 0..1000000 step 1", &HashMap::new(), false).is_err());
+    }
+
+    #[test]
+    fn quotes_the_csv_fields() {
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(format_table(&["x".to_string()], &[vec![None]], true), "x\n");
+    }
+}
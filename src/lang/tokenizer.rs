@@ -1,18 +1,34 @@
 use std::f64::consts::*;
+use std::fmt;
 use crate::lang::tokenizer::State::{No, Num, Special, Word};
+use crate::lang::{backend, matrix, random, statistics};
+use crate::lang::backend::Backend;
+use crate::lang::bigint::BigInt;
+use crate::lang::decimal::Decimal;
+use crate::lang::numeric::Numeric;
+use crate::lang::value::Value;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Name(String),
     Number(f64),
+    Decimal(Decimal), // Number of $backend decimal, with all its digits
+    Integer(BigInt), // Integer too large for a float, or exact value of an integer once computed
     Compare(Compare),
     Operator(Operator),
     Operation(Operation),
     Colon,
+    Comma,
     Key(Keyword),
+    Function(Function),
     ParenOpen,
     ParenClose,
-    Paren(Vec<Token>)
+    Paren(Vec<Token>),
+    BracketOpen,
+    BracketClose,
+    Bracket(Vec<Token>), // List literal "[1, 2]" or index "l[0]"
+    List(Vec<f64>), // Value of a list once computed
+    Matrix(Vec<Vec<f64>>), // Value of a matrix once computed
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -26,19 +42,32 @@ pub enum Compare {
 }
 
 impl Compare {
-    pub fn compare(&self, a:f64, b:f64) -> bool {
-        match *&self {
-            Compare::Equal => {return a == b}
-            Compare::NotEqual => {return  a != b}
-            Compare::LessThan => { return a < b}
-            Compare::GreaterThan => {return a > b}
-            Compare::LessThanOrEqual => {return a <= b}
-            Compare::GreaterThanOrEqual => {return a >= b}
+    pub fn compare<T: PartialOrd>(&self, a: T, b: T) -> bool {
+        match self {
+            Compare::Equal => a == b,
+            Compare::NotEqual => a != b,
+            Compare::LessThan => a < b,
+            Compare::GreaterThan => a > b,
+            Compare::LessThanOrEqual => a <= b,
+            Compare::GreaterThanOrEqual => a >= b,
         }
     }
 
 }
 
+impl fmt::Display for Compare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Compare::Equal => "==",
+            Compare::NotEqual => "!=",
+            Compare::LessThan => "<",
+            Compare::GreaterThan => ">",
+            Compare::LessThanOrEqual => "<=",
+            Compare::GreaterThanOrEqual => ">=",
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Operator {
     Plus,
@@ -47,26 +76,70 @@ pub enum Operator {
     Divide,
     Exponent,
     Log,
-    Factorial,
+    Modulo,
+    IntDivide,
+    And,
+    Or,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
+    Factorial, // Postfix, "5!" is read as "5 ! 0"
     None,
 }
 
 impl Operator {
+    // Computed by the backend of the session
     pub fn operate(&self, a: f64, b: f64) -> Result<f64, String> {
+        backend::selected().operate(self, a, b)
+    }
+
+    // Arithmetic on any Numeric, f64 for the default backend
+    pub fn compute<N: Numeric>(&self, a: N, b: N) -> Result<N, String> {
         match self {
-            Operator::Plus => Ok(a + b),
-            Operator::Minus => Ok(a - b),
-            Operator::Multiply => Ok(a * b),
+            Operator::Plus => a.add(b),
+            Operator::Minus => a.subtract(b),
+            Operator::Multiply => a.multiply(b),
             Operator::Divide => {
-                if b != 0.0 {
-                   Ok( a / b)
+                if !b.is_zero() {
+                    a.divide(b)
                 } else {
                     Err(format!("Division by zero: {}/{}", a, b))
                 }
             }
-            Operator::Exponent => Ok(a.powf(b)),
-            Operator::Log => Ok(a.log(b)),
-            Operator::Factorial => Err("Factorials not supported for now".into()), //TODO
+            Operator::Exponent => a.power(b),
+            Operator::Log => a.log(b),
+            // Same sign as the divisor: -7 % 3 = 2
+            Operator::Modulo => {
+                if !b.is_zero() {
+                    a.clone().subtract(b.clone().multiply(a.divide(b)?.floor()?)?)
+                } else {
+                    Err(format!("Modulo by zero: {}%{}", a, b))
+                }
+            }
+            Operator::IntDivide => {
+                if !b.is_zero() {
+                    a.divide(b)?.floor()
+                } else {
+                    Err(format!("Division by zero: {} div {}", a, b))
+                }
+            }
+            Operator::And => N::from_f64((integer(a.to_f64())? & integer(b.to_f64())?) as f64),
+            Operator::Or => N::from_f64((integer(a.to_f64())? | integer(b.to_f64())?) as f64),
+            Operator::Xor => N::from_f64((integer(a.to_f64())? ^ integer(b.to_f64())?) as f64),
+            Operator::ShiftLeft => {
+                let value = integer(a.to_f64())?;
+                let shifted = 2i64.checked_pow(shift(b.to_f64())?).and_then(|power| value.checked_mul(power));
+                N::from_f64(shifted.ok_or(format!("Overflow of {} << {}", a, b))? as f64)
+            }
+            Operator::ShiftRight => N::from_f64((integer(a.to_f64())? >> shift(b.to_f64())?) as f64),
+            // Computed with the numbers of the backend, exact integers are computed by the Value
+            Operator::Factorial => {
+                let mut result = N::from_f64(1.0)?;
+                for k in 2..=factorial_argument(a.to_f64())? {
+                    result = result.multiply(N::from_f64(k as f64)?)?;
+                }
+                Ok(result)
+            }
             Operator::None => Err("Inter: can't be none".into()) //TODO
         }
     }
@@ -75,17 +148,78 @@ impl Operator {
         self.priority() >= b.priority()
     }
 
-    fn priority(&self) -> i32 {
+    pub fn priority(&self) -> i32 {
         match self {
+            // Only found in "(n ! 0)" where it's the single operator
+            Operator::Factorial => 4,
             Operator::Exponent  | Operator::Log => 3,
-            Operator::Multiply | Operator::Divide => 2,
+            Operator::Multiply | Operator::Divide | Operator::Modulo | Operator::IntDivide => 2,
             Operator::Plus | Operator::Minus => 1,
+            // Bitwise operators come after the arithmetic: "a + 1 & b" is "(a + 1) & b"
+            Operator::And => -1,
+            Operator::Xor => -2,
+            Operator::Or => -3,
             Operator::None => 4,
             _ => 0,
         }
     }
 }
 
+const MAX_FACTORIAL: u32 = 10000;
+// Largest integer a float stores exactly, 2^53
+pub const MAX_EXACT: f64 = 9007199254740992.0;
+
+// Bitwise operators only work on integers, exactly stored up to 2^53 in a float
+fn integer(x: f64) -> Result<i64, String> {
+    if x.fract() != 0.0 || !x.is_finite() {
+        return Err(format!("Bitwise operations need integers, {} is not", x))
+    }
+    if x.abs() > MAX_EXACT {
+        return Err(format!("{} is too large for a bitwise operation on floats, they are exact up to 2^53", x))
+    }
+    Ok(x as i64)
+}
+
+// 10000! already has 35660 digits
+pub fn factorial_argument(x: f64) -> Result<u32, String> {
+    if x.fract() != 0.0 || x < 0.0 {
+        return Err(format!("Factorial of {}, it is only defined for the natural numbers", x))
+    }
+    if x > MAX_FACTORIAL as f64 {
+        return Err(format!("Factorial of {} is too large, the limit is {}", x, MAX_FACTORIAL))
+    }
+    Ok(x as u32)
+}
+
+fn shift(x: f64) -> Result<u32, String> {
+    if x.fract() != 0.0 || !(0.0..64.0).contains(&x) {
+        return Err(format!("Invalid shift of {} bits", x))
+    }
+    Ok(x as u32)
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Exponent => "^",
+            Operator::Log => "//",
+            Operator::Modulo => "%",
+            Operator::IntDivide => "div",
+            Operator::And => "&",
+            Operator::Or => "|",
+            Operator::Xor => "xor",
+            Operator::ShiftLeft => "<<",
+            Operator::ShiftRight => ">>",
+            Operator::Factorial => "!",
+            Operator::None => "",
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operation {
     Assign,
@@ -93,12 +227,245 @@ pub enum Operation {
     SubtractVar,
     MultiplyVar,
     DivideVar,
+    ModuloVar,
+    AndVar,
+    OrVar,
+    XorVar,
+    ShiftLeftVar,
+    ShiftRightVar,
+}
+
+impl Operation {
+    // "a += 2" is "a = a + 2"
+    pub fn operator(&self) -> Option<Operator> {
+        match self {
+            Operation::Assign => None,
+            Operation::AddVar => Some(Operator::Plus),
+            Operation::SubtractVar => Some(Operator::Minus),
+            Operation::MultiplyVar => Some(Operator::Multiply),
+            Operation::DivideVar => Some(Operator::Divide),
+            Operation::ModuloVar => Some(Operator::Modulo),
+            Operation::AndVar => Some(Operator::And),
+            Operation::OrVar => Some(Operator::Or),
+            Operation::XorVar => Some(Operator::Xor),
+            Operation::ShiftLeftVar => Some(Operator::ShiftLeft),
+            Operation::ShiftRightVar => Some(Operator::ShiftRight),
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Operation::Assign => "=",
+            Operation::AddVar => "+=",
+            Operation::SubtractVar => "-=",
+            Operation::MultiplyVar => "*=",
+            Operation::DivideVar => "/=",
+            Operation::ModuloVar => "%=",
+            Operation::AndVar => "&=",
+            Operation::OrVar => "|=",
+            Operation::XorVar => "xor=",
+            Operation::ShiftLeftVar => "<<=",
+            Operation::ShiftRightVar => ">>=",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sqrt,
+    Ln,
+    Exp,
+    Abs,
+    Integrate,
+    Sum,
+    Prod,
+    Len,
+    Append,
+    Mean,
+    Median,
+    Stdev,
+    Variance,
+    Pstdev,
+    Pvariance,
+    Min,
+    Max,
+    Quantile,
+    Linreg,
+    Transpose,
+    Det,
+    Inv,
+    Linsolve,
+    Rand,
+    Randint,
+    Randn,
+}
+
+impl Function {
+    // integrate(expression, x, a, b), sum(...) and prod(...) evaluate their expression for many values of a variable
+    pub fn binds_variable(&self) -> bool {
+        matches!(self, Function::Integrate | Function::Sum | Function::Prod)
+    }
+
+    // rand(), randint(a, b) and randn() give a new value at each call, they are never computed in advance
+    pub fn is_random(&self) -> bool {
+        matches!(self, Function::Rand | Function::Randint | Function::Randn)
+    }
+
+    pub fn apply(&self, args: Vec<Value>) -> Result<Value, String> {
+        let number = |result: Result<f64, String>| result.map(Value::Number);
+        // Only the functions of one number keep the decimals, the others take them as floats like the integers
+        let single = args.len() == 1;
+        let args: Vec<Value> = args.into_iter().map(|arg| match arg {
+            Value::Decimal(_) if single => arg,
+            arg => arg.float(),
+        }).collect();
+        match (self, &args[..]) {
+            // sum(expression, k, from, to) is computed by the runner, sum(l) adds the elements of a list
+            (Function::Sum, [Value::List(list)]) => Ok(Value::Number(list.iter().sum())),
+            (Function::Prod, [Value::List(list)]) => Ok(Value::Number(list.iter().product())),
+            _ if self.binds_variable() => Err(format!("{}(expression, variable, from, to) can't be applied to values", self)),
+            // append([1, 2], 3) = [1, 2, 3], append([1], [2, 3]) = [1, 2, 3]
+            (Function::Append, [Value::List(list), Value::Number(num)]) => Ok(Value::List([&list[..], &[*num]].concat())),
+            (Function::Append, [Value::List(list), Value::List(other)]) => Ok(Value::List([&list[..], &other[..]].concat())),
+            (Function::Append, [_, _]) => Err("append takes a list and the value to add".into()),
+            (Function::Quantile, [Value::List(list), Value::Number(p)]) => number(statistics::quantile(list, *p)),
+            (Function::Quantile, [_, _]) => Err("quantile takes a list and a number between 0 and 1".into()),
+            // linreg(xs, ys) = [slope, intercept, r²]
+            (Function::Linreg, [Value::List(xs), Value::List(ys)]) => {
+                let (slope, intercept, r2) = statistics::linear_regression(xs, ys)?;
+                Ok(Value::List(vec![slope, intercept, r2]))
+            }
+            (Function::Linreg, [_, _]) => Err("linreg takes the list of x and the list of y".into()),
+            // linsolve(A, b) = x such that A * x = b
+            (Function::Linsolve, [Value::Matrix(a), Value::List(b)]) => Ok(Value::List(matrix::solve(a, b)?)),
+            (Function::Linsolve, [_, _]) => Err("linsolve takes a matrix and a list".into()),
+            (Function::Randint, [Value::Number(a), Value::Number(b)]) => number(random::integer(*a, *b)),
+            (Function::Randint, [_, _]) => Err("randint takes two integers".into()),
+            (Function::Append | Function::Quantile | Function::Linreg | Function::Linsolve | Function::Randint, _) => Err(format!("{} takes 2 arguments, {} given", self, args.len())),
+            (Function::Transpose, [Value::Matrix(a)]) => Ok(Value::Matrix(matrix::transpose(a))),
+            // A list is a row, its transpose is a column
+            (Function::Transpose, [Value::List(list)]) => Ok(Value::Matrix(list.iter().map(|x| vec![*x]).collect())),
+            (Function::Det, [Value::Matrix(a)]) => number(matrix::determinant(a)),
+            (Function::Inv, [Value::Matrix(a)]) => Ok(Value::Matrix(matrix::inverse(a)?)),
+            (Function::Transpose | Function::Det | Function::Inv, [x]) => Err(format!("{} takes a matrix, not a {}", self, x.kind())),
+            (Function::Rand, []) => Ok(Value::Number(random::uniform())),
+            (Function::Randn, []) => Ok(Value::Number(random::normal())),
+            (Function::Rand | Function::Randn, _) => Err(format!("{} takes no argument, {} given", self, args.len())),
+            (Function::Len, [Value::List(list)]) => Ok(Value::Number(list.len() as f64)),
+            (Function::Len, [Value::Matrix(matrix)]) => Ok(Value::Number(matrix.len() as f64)),
+            (Function::Mean, [Value::List(list)]) => number(statistics::mean(list)),
+            (Function::Median, [Value::List(list)]) => number(statistics::median(list)),
+            (Function::Stdev, [Value::List(list)]) => number(statistics::stdev(list, true)),
+            (Function::Variance, [Value::List(list)]) => number(statistics::variance(list, true)),
+            (Function::Pstdev, [Value::List(list)]) => number(statistics::stdev(list, false)),
+            (Function::Pvariance, [Value::List(list)]) => number(statistics::variance(list, false)),
+            (Function::Min, [Value::List(list)]) => number(statistics::min(list)),
+            (Function::Max, [Value::List(list)]) => number(statistics::max(list)),
+            (_, [x @ (Value::Number(_) | Value::Decimal(_) | Value::Matrix(_))]) if self.takes_list() => Err(format!("{} takes a list, not a {}", self, x.kind())),
+            (_, [Value::Decimal(x)]) => Ok(Value::Decimal(self.apply_number(x.clone())?)),
+            // Functions of numbers are applied to every element of a list
+            (_, [x]) => x.clone().map(|x| backend::selected().apply(self, x)),
+            _ => Err(format!("{} takes 1 argument, {} given", self, args.len())),
+        }
+    }
+
+    fn takes_list(&self) -> bool {
+        matches!(self, Function::Len | Function::Mean | Function::Median | Function::Stdev | Function::Variance
+            | Function::Pstdev | Function::Pvariance | Function::Min | Function::Max)
+    }
+
+    // Computed with the Numeric of the backend
+    pub fn apply_number<N: Numeric>(&self, x: N) -> Result<N, String> {
+        match self {
+            Function::Sin => x.map(f64::sin),
+            Function::Cos => x.map(f64::cos),
+            Function::Tan => x.map(f64::tan),
+            Function::Asin => x.map(f64::asin),
+            Function::Acos => x.map(f64::acos),
+            Function::Atan => x.map(f64::atan),
+            Function::Sqrt => {
+                if x >= N::from_f64(0.0)? {
+                    x.sqrt()
+                } else {
+                    Err(format!("Square root of a negative number: sqrt({})", x))
+                }
+            }
+            Function::Ln => {
+                if x > N::from_f64(0.0)? {
+                    x.ln()
+                } else {
+                    Err(format!("Logarithm of a non positive number: ln({})", x))
+                }
+            }
+            Function::Exp => x.exp(),
+            Function::Abs => x.abs(),
+            _ => Err(format!("{} is not a function of a number", self)),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Asin => "asin",
+            Function::Acos => "acos",
+            Function::Atan => "atan",
+            Function::Sqrt => "sqrt",
+            Function::Ln => "ln",
+            Function::Exp => "exp",
+            Function::Abs => "abs",
+            Function::Integrate => "integrate",
+            Function::Sum => "sum",
+            Function::Prod => "prod",
+            Function::Len => "len",
+            Function::Append => "append",
+            Function::Mean => "mean",
+            Function::Median => "median",
+            Function::Stdev => "stdev",
+            Function::Variance => "variance",
+            Function::Pstdev => "pstdev",
+            Function::Pvariance => "pvariance",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Quantile => "quantile",
+            Function::Linreg => "linreg",
+            Function::Transpose => "transpose",
+            Function::Det => "det",
+            Function::Inv => "inv",
+            Function::Linsolve => "linsolve",
+            Function::Rand => "rand",
+            Function::Randint => "randint",
+            Function::Randn => "randn",
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Keyword {
     If,
     While,
+    Solve,
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Keyword::If => "if",
+            Keyword::While => "while",
+            Keyword::Solve => "solve",
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -115,6 +482,12 @@ pub(crate) fn tokenize2(input: &str) -> Result<Vec<Token>, String> {
         if state == Word {
             if !char.is_whitespace() && (char.is_alphanumeric() || char == '_') {
                 current_string.push(char)
+            } else if current_string == "xor" && char == '=' {
+                // The only compound assignment written with a word
+                tokens.push(Token::Operation(Operation::XorVar));
+                current_string = String::new();
+                state = No;
+                continue;
             } else {
                 tokens.push(tokenize_name(current_string.clone()));
                 current_string = String::new();
@@ -137,7 +510,8 @@ pub(crate) fn tokenize2(input: &str) -> Result<Vec<Token>, String> {
             }
         }
         if state == Special {
-            if is_special(char) {
+            // "5!+1" is "5! + 1", "!=" stays a comparison
+            if is_special(char) && !(current_string == "!" && char != '=') {
                 current_string.push(char)
             } else {
                 tokens.push(tokenize_special(current_string.clone())?);
@@ -158,8 +532,14 @@ pub(crate) fn tokenize2(input: &str) -> Result<Vec<Token>, String> {
                 tokens.push(Token::ParenOpen);
             } else if char == ')' {
                 tokens.push(Token::ParenClose);
+            } else if char == '[' {
+                tokens.push(Token::BracketOpen);
+            } else if char == ']' {
+                tokens.push(Token::BracketClose);
             } else if char == ':' {
                 tokens.push(Token::Colon);
+            } else if char == ',' {
+                tokens.push(Token::Comma);
             } else if is_special(char) {
                 current_string.push(char);
                 state = Special;
@@ -170,6 +550,13 @@ pub(crate) fn tokenize2(input: &str) -> Result<Vec<Token>, String> {
             }
         }
     }
+    // Flush the last token when the input doesn't end with a separator
+    match state {
+        Word => tokens.push(tokenize_name(current_string)),
+        Num => tokens.push(tokenize_num(current_string)),
+        Special => tokens.push(tokenize_special(current_string)?),
+        No => {}
+    }
 
     Ok(tokens)
 }
@@ -179,14 +566,62 @@ fn tokenize_name(name: String) -> Token {
             "if" => Token::Key(Keyword::If),
             "wl" | "while" => Token::Key(Keyword::While),
             "del" => Token::Key(Keyword::While),
-            "PI" => Token::Number(PI),
-            "e" => Token::Number(E),
+            "solve" => Token::Key(Keyword::Solve),
+            "div" => Token::Operator(Operator::IntDivide),
+            "xor" => Token::Operator(Operator::Xor),
+            "PI" => match backend::selected() {
+                Backend::Decimal(digits) => Token::Decimal(Decimal::pi(digits)),
+                _ => Token::Number(PI),
+            },
+            "e" => match backend::selected() {
+                Backend::Decimal(digits) => Token::Decimal(Decimal::e(digits)),
+                _ => Token::Number(E),
+            },
+            "sin" => Token::Function(Function::Sin),
+            "cos" => Token::Function(Function::Cos),
+            "tan" => Token::Function(Function::Tan),
+            "asin" => Token::Function(Function::Asin),
+            "acos" => Token::Function(Function::Acos),
+            "atan" => Token::Function(Function::Atan),
+            "sqrt" => Token::Function(Function::Sqrt),
+            "ln" => Token::Function(Function::Ln),
+            "exp" => Token::Function(Function::Exp),
+            "abs" => Token::Function(Function::Abs),
+            "integrate" => Token::Function(Function::Integrate),
+            "sum" => Token::Function(Function::Sum),
+            "prod" => Token::Function(Function::Prod),
+            "len" => Token::Function(Function::Len),
+            "append" => Token::Function(Function::Append),
+            "mean" => Token::Function(Function::Mean),
+            "median" => Token::Function(Function::Median),
+            "stdev" => Token::Function(Function::Stdev),
+            "variance" => Token::Function(Function::Variance),
+            "pstdev" => Token::Function(Function::Pstdev),
+            "pvariance" => Token::Function(Function::Pvariance),
+            "min" => Token::Function(Function::Min),
+            "max" => Token::Function(Function::Max),
+            "quantile" => Token::Function(Function::Quantile),
+            "linreg" => Token::Function(Function::Linreg),
+            "transpose" => Token::Function(Function::Transpose),
+            "det" => Token::Function(Function::Det),
+            "inv" => Token::Function(Function::Inv),
+            "linsolve" => Token::Function(Function::Linsolve),
+            "rand" => Token::Function(Function::Rand),
+            "randint" => Token::Function(Function::Randint),
+            "randn" => Token::Function(Function::Randn),
             _ => Token::Name(name),
         }
 }
 
 fn tokenize_num(name: String) -> Token {
-    Token::Number(name.parse::<f64>().expect("Unable to convert to num"))
+    match backend::selected() {
+        Backend::Decimal(_) => Token::Decimal(Decimal::parse(&name).expect("Unable to convert to num")),
+        // Integers above 2^53 aren't exact as floats, they keep all their digits
+        Backend::Double if !name.contains('.') && name.len() > 15 && name.parse::<f64>().is_ok_and(|x| x > MAX_EXACT) => {
+            Token::Integer(BigInt::parse(&name).expect("Unable to convert to num"))
+        }
+        _ => Token::Number(name.parse::<f64>().expect("Unable to convert to num")),
+    }
 }
 
 fn tokenize_special(name: String) -> Result<Token, String> {
@@ -197,8 +632,15 @@ fn tokenize_special(name: String) -> Result<Token, String> {
         "/" => Token::Operator(Operator::Divide),
         "**" | "^" => Token::Operator(Operator::Exponent),
         "//" => Token::Operator(Operator::Log),
+        "!" => Token::Operator(Operator::Factorial),
+        "%" => Token::Operator(Operator::Modulo),
+        "&" => Token::Operator(Operator::And),
+        "|" => Token::Operator(Operator::Or),
+        "<<" => Token::Operator(Operator::ShiftLeft),
+        ">>" => Token::Operator(Operator::ShiftRight),
 
         "==" => Token::Compare(Compare::Equal),
+        "!=" | "<>" => Token::Compare(Compare::NotEqual),
         "<" => Token::Compare(Compare::LessThan),
         "<=" => Token::Compare(Compare::LessThanOrEqual),
         ">" => Token::Compare(Compare::GreaterThan),
@@ -209,11 +651,52 @@ fn tokenize_special(name: String) -> Result<Token, String> {
         "-=" => Token::Operation(Operation::SubtractVar),
         "*=" => Token::Operation(Operation::MultiplyVar),
         "/=" => Token::Operation(Operation::DivideVar),
+        "%=" => Token::Operation(Operation::ModuloVar),
+        "&=" => Token::Operation(Operation::AndVar),
+        "|=" => Token::Operation(Operation::OrVar),
+        "<<=" => Token::Operation(Operation::ShiftLeftVar),
+        ">>=" => Token::Operation(Operation::ShiftRightVar),
 
         _ => return Err(format!("operation {} not supported", name))
     })
 }
 
-fn is_special(c: char) -> bool {
+pub fn is_special(c: char) -> bool {
     c == '<' || c == '>' || c == '=' || c == '*' || c == '/' || c == '-' || c == '+' || c == '^'
+        || c == '%' || c == '&' || c == '|' || c == '!'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_the_word_compound_assignment() {
+        assert_eq!(tokenize2("a xor= 3").unwrap(), vec![Token::Name("a".into()), Token::Operation(Operation::XorVar), Token::Number(3.0)]);
+        assert_eq!(tokenize2("a = b xor 3").unwrap()[3], Token::Operator(Operator::Xor));
+    }
+
+    #[test]
+    fn computes_bitwise_operators() {
+        assert_eq!(Operator::Xor.operate(6.0, 3.0).unwrap(), 5.0);
+        assert_eq!(Operator::ShiftLeft.operate(3.0, 2.0).unwrap(), 12.0);
+        assert_eq!(Operator::ShiftRight.operate(-8.0, 1.0).unwrap(), -4.0);
+        assert_eq!(Operator::Modulo.operate(-7.0, 3.0).unwrap(), 2.0);
+        assert_eq!(Operator::IntDivide.operate(7.0, -2.0).unwrap(), -4.0);
+    }
+
+    #[test]
+    fn reports_the_cause_of_a_shift_error() {
+        assert_eq!(Operator::ShiftLeft.operate(1.5, 2.0).unwrap_err(), "Bitwise operations need integers, 1.5 is not");
+        assert_eq!(Operator::ShiftLeft.operate(3.0, 62.0).unwrap_err(), "Overflow of 3 << 62");
+        assert!(Operator::ShiftLeft.operate(1.0, 64.0).is_err());
+        assert_eq!(Operator::And.operate(1e20, 1.0).unwrap_err(), "100000000000000000000 is too large for a bitwise operation on floats, they are exact up to 2^53");
+    }
+
+    #[test]
+    fn rejects_the_functions_not_of_a_number() {
+        assert_eq!(Function::Sqrt.apply_number(4.0), Ok(2.0));
+        assert_eq!(Function::Len.apply_number(4.0), Err("len is not a function of a number".to_string()));
+        assert!(Function::Randint.apply_number(4.0).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,203 @@
+/*
+Export to the Casio Basic of the fx-9860G/fx-CG in its text form ("→", "×", "÷", "◢", ...).
+Variables become the real variables A to Z, r and θ, blocks end with IfEnd and WhileEnd
+and a displayed value waits for EXE with "◢"
+ */
+
+use std::f64::consts::{E, PI};
+use crate::lang::tokenizer::{Compare, Function, Operator};
+use crate::lang::transpile::{export, letter_variable, wrap, Export, Target, ATOM, NEGATION, POWER, PRODUCT, SUM};
+
+const LETTERS: &[&str] = &["A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M",
+    "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "r", "θ"];
+
+pub fn to_casio_basic(script: &str) -> Export {
+    export(script, CasioBasic { radian: false })
+}
+
+struct CasioBasic {
+    radian: bool, // Set when a trigonometric function is used
+}
+
+impl Target for CasioBasic {
+    fn language(&self) -> &'static str {
+        "Casio Basic"
+    }
+
+    fn variable(&self, name: &str, taken: &[(String, String)]) -> Result<String, String> {
+        letter_variable(name, taken, LETTERS, self.language())
+    }
+
+    fn number(&mut self, num: f64) -> (String, u8) {
+        if num == PI {
+            return ("π".to_string(), ATOM)
+        }
+        if num == E {
+            return ("e".to_string(), ATOM)
+        }
+        // Large and tiny numbers are written with the exponent "ᴇ"
+        let text = if num != 0.0 && (num.abs() >= 1e10 || num.abs() < 1e-5) {
+            format!("{:e}", num.abs()).replace('e', "ᴇ")
+        } else {
+            format!("{}", num.abs())
+        };
+        if num < 0.0 {
+            (format!("-{}", text), NEGATION)
+        } else {
+            (text, ATOM)
+        }
+    }
+
+    fn negation(&self, a: (String, u8)) -> (String, u8) {
+        (format!("-{}", wrap(a, POWER)), NEGATION)
+    }
+
+    fn operation(&mut self, o: &Operator, a: (String, u8), b: (String, u8)) -> Result<(String, u8), String> {
+        Ok(match o {
+            Operator::Plus => (format!("{}+{}", wrap(a, SUM), wrap(b, SUM)), SUM),
+            Operator::Minus => (format!("{}-{}", wrap(a, SUM), wrap(b, PRODUCT)), SUM),
+            Operator::Multiply => (format!("{}×{}", wrap(a, PRODUCT), wrap(b, PRODUCT)), PRODUCT),
+            Operator::Divide => (format!("{}÷{}", wrap(a, PRODUCT), wrap(b, NEGATION)), PRODUCT),
+            // The left operand of ^ is computed before a minus sign: "-2^2" is -4
+            Operator::Exponent => (format!("{}^{}", wrap(a, POWER), wrap(b, ATOM)), POWER),
+            Operator::Log => (format!("log({})÷log({})", a.0, b.0), PRODUCT),
+            // Intg is the floor, the modulo keeps the sign of the divisor like Malors
+            Operator::Modulo => {
+                let (a, b) = (wrap(a, ATOM), wrap(b, ATOM));
+                (format!("{}-{}×Intg({}÷{})", a, b, a, b), SUM)
+            }
+            Operator::IntDivide => (format!("Intg({}÷{})", wrap(a, PRODUCT), wrap(b, NEGATION)), ATOM),
+            Operator::ShiftLeft => (format!("{}×2^{}", wrap(a, PRODUCT), wrap(b, ATOM)), PRODUCT),
+            Operator::ShiftRight => (format!("Intg({}÷2^{})", wrap(a, PRODUCT), wrap(b, ATOM)), ATOM),
+            Operator::And | Operator::Or | Operator::Xor => {
+                return Err(format!("Bitwise {} can't be translated, the And, Or and Xor of Casio Basic are logical", o))
+            }
+            Operator::Factorial | Operator::None => return Err(format!("Operator {} can't be translated to Casio Basic", o)),
+        })
+    }
+
+    fn call(&mut self, function: &Function, args: Vec<String>) -> Result<String, String> {
+        let count = args.len();
+        Ok(match (function, count) {
+            (Function::Sin | Function::Cos | Function::Tan | Function::Asin | Function::Acos | Function::Atan, 1) => {
+                self.radian = true;
+                let name = match function {
+                    Function::Sin => "sin",
+                    Function::Cos => "cos",
+                    Function::Tan => "tan",
+                    Function::Asin => "sin⁻¹",
+                    Function::Acos => "cos⁻¹",
+                    _ => "tan⁻¹",
+                };
+                format!("{}({})", name, args[0])
+            }
+            (Function::Sqrt, 1) => format!("√({})", args[0]),
+            (Function::Ln, 1) => format!("ln({})", args[0]),
+            (Function::Exp, 1) => format!("e^({})", args[0]),
+            (Function::Abs, 1) => format!("Abs({})", args[0]),
+            // ∫( only integrates over X
+            (Function::Integrate, 4) if args[1] == "X" => format!("∫({},{},{})", args[0], args[2], args[3]),
+            (Function::Integrate, 4) => return Err("Casio Basic only integrates over the variable X".into()),
+            (Function::Sum, 4) => format!("Σ({})", args.join(",")),
+            (Function::Rand, 0) => "Ran#".to_string(),
+            (Function::Randint, 2) => format!("RanInt#({})", args.join(",")),
+            (Function::Randn, 0) => "RanNorm#(1,0)".to_string(),
+            _ => return Err(format!("{}() with {} argument(s) can't be translated to Casio Basic", function, count)),
+        })
+    }
+
+    // "0 < x <= 10" => "0<X And X≤10"
+    fn condition(&self, first: String, rest: Vec<(Compare, String)>) -> String {
+        let mut a = first;
+        let mut comparisons = Vec::new();
+        for (c, b) in rest {
+            comparisons.push(format!("{}{}{}", a, compare(&c), b));
+            a = b;
+        }
+        comparisons.join(" And ")
+    }
+
+    fn assign(&self, var: &str, expression: String) -> String {
+        format!("{}→{}", expression, var)
+    }
+
+    fn out(&self, names: Vec<String>) -> Vec<String> {
+        names.into_iter().map(|name| format!("{}◢", name)).collect()
+    }
+
+    fn if_block(&self, condition: String, body: Vec<String>) -> Vec<String> {
+        let mut lines = vec![format!("If {}", condition), "Then".to_string()];
+        lines.extend(body);
+        lines.push("IfEnd".to_string());
+        lines
+    }
+
+    fn while_block(&self, condition: String, body: Vec<String>) -> Vec<String> {
+        let mut lines = vec![format!("While {}", condition)];
+        lines.extend(body);
+        lines.push("WhileEnd".to_string());
+        lines
+    }
+
+    // Solve(expression, guess) searches a root in X from the guess like Malors
+    fn solve(&self, var: &str, equation: String) -> Result<Vec<String>, String> {
+        if var != "X" {
+            return Err("Casio Basic only solves equations in the variable X".into())
+        }
+        Ok(vec![format!("Solve({},X)→X", equation)])
+    }
+
+    // The trigonometric functions of Malors work in radians
+    fn prelude(&self) -> Vec<String> {
+        if self.radian { vec!["Rad".to_string()] } else { vec![] }
+    }
+}
+
+fn compare(c: &Compare) -> &'static str {
+    match c {
+        Compare::Equal => "=",
+        Compare::NotEqual => "≠",
+        Compare::LessThan => "<",
+        Compare::GreaterThan => ">",
+        Compare::LessThanOrEqual => "≤",
+        Compare::GreaterThanOrEqual => "≥",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_a_script() {
+        let export = to_casio_basic("total = 0\ni = 1\nwhile i <= 10: total += i^2: i += 1\nif total > 300: total\nr = sin(PI / 4) * -2\n");
+        assert_eq!(export.source, "Rad\n0→T\n1→I\nWhile I≤10\nT+I^2→T\nI+1→I\nWhileEnd\nIf T>300\nThen\nT◢\nIfEnd\nsin(π÷4)×-2→R\n");
+        assert_eq!(export.names, vec![("total".to_string(), "T".to_string()), ("i".to_string(), "I".to_string()), ("r".to_string(), "R".to_string())]);
+        assert!(export.issues.is_empty());
+    }
+
+    #[test]
+    fn writes_the_priorities_and_numbers_of_the_calculator() {
+        assert_eq!(to_casio_basic("a = (1 - 2) * 3 ^ (4 + 5) / -a").source, "(1-2)×3^(4+5)÷-A→A\n");
+        assert_eq!(to_casio_basic("a = 0.000001 + 25000000000").source, "1ᴇ-6+2.5ᴇ10→A\n");
+        assert_eq!(to_casio_basic("a = 7 % 3\nb = 7 div 3").source, "7-3×Intg(7÷3)→A\nIntg(7÷3)→B\n");
+        assert_eq!(to_casio_basic("if 0 < a <= 10: a").source, "If 0<A And A≤10\nThen\nA◢\nIfEnd\n");
+    }
+
+    #[test]
+    fn solves_and_integrates_over_x() {
+        assert_eq!(to_casio_basic("x = 1\nsolve x: x^2 == 2").source, "1→X\nSolve(X^2-2,X)→X\n");
+        assert_eq!(to_casio_basic("a = integrate(x^2, x, 0, 1)").source, "∫(X^2,0,1)→A\n");
+        let export = to_casio_basic("y = 1\nsolve y: y^2 == 2\na = integrate(t, t, 0, 1)");
+        assert_eq!(export.source, "1→Y\n");
+        assert_eq!(export.issues.iter().map(|(line, _)| *line).collect::<Vec<usize>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn reports_the_bitwise_operators() {
+        let export = to_casio_basic("a = 1\nb = 6 & 3");
+        assert_eq!(export.source, "1→A\n");
+        assert_eq!(export.issues.len(), 1);
+        assert!(export.issues[0].1.contains("Bitwise"));
+    }
+}
@@ -0,0 +1,223 @@
+/*
+Transpilers of Malors scripts to the languages of calculators.
+Every line is parsed into a LineType and translated on its own, the lines that can't be translated
+are left out of the program and reported with their number.
+The Translator walks the lines and the expression threes, a Target writes them in its language
+ */
+
+pub(crate) mod ti_basic;
+pub(crate) mod ti_8xp;
+pub(crate) mod casio_basic;
+pub(crate) mod python;
+pub(crate) mod ti_import;
+
+use crate::lang::calculator::{parse_expression, post_process_paren, Expression, Possible};
+use crate::lang::formatter::format_token;
+use crate::lang::line_type::{construct_line_type, Condition, LineType};
+use crate::lang::numeric::Numeric;
+use crate::lang::tokenizer::{tokenize2, Compare, Function, Operation, Operator, Token};
+
+// Priorities of the texts, a text is put between parenthesis when its priority is below the one needed
+pub const SUM: u8 = 1;
+pub const PRODUCT: u8 = 2;
+pub const NEGATION: u8 = 3;
+pub const POWER: u8 = 4;
+pub const ATOM: u8 = 5;
+
+pub struct Export {
+    pub source: String,
+    pub names: Vec<(String, String)>, // (Malors name, name on the calculator)
+    pub issues: Vec<(usize, String)>, // (line number, reason)
+}
+
+pub trait Target {
+    // Name of the language in the reports
+    fn language(&self) -> &'static str;
+    // Name of a variable not translated yet, the ones already given are in "taken"
+    fn variable(&self, name: &str, taken: &[(String, String)]) -> Result<String, String>;
+    fn number(&mut self, num: f64) -> (String, u8);
+    fn negation(&self, a: (String, u8)) -> (String, u8);
+    fn operation(&mut self, o: &Operator, a: (String, u8), b: (String, u8)) -> Result<(String, u8), String>;
+    fn call(&mut self, function: &Function, args: Vec<String>) -> Result<String, String>;
+    fn condition(&self, first: String, rest: Vec<(Compare, String)>) -> String;
+    // "x += 1" when the language has the compound assignment, None to write "x = x + 1"
+    fn compound(&mut self, _var: &str, _operation: &Operation, _expression: &str) -> Option<String> {
+        None
+    }
+    fn assign(&self, var: &str, expression: String) -> String;
+    fn out(&self, names: Vec<String>) -> Vec<String>;
+    fn if_block(&self, condition: String, body: Vec<String>) -> Vec<String>;
+    fn while_block(&self, condition: String, body: Vec<String>) -> Vec<String>;
+    fn solve(&self, var: &str, equation: String) -> Result<Vec<String>, String>;
+    // Lines needed before the program, like a mode or an import
+    fn prelude(&self) -> Vec<String>;
+}
+
+pub fn export<T: Target>(script: &str, target: T) -> Export {
+    let mut translator = Translator { target, names: Vec::new() };
+    let mut lines = Vec::new();
+    let mut issues = Vec::new();
+    for (i, line) in script.lines().enumerate() {
+        let translated = parse(line).and_then(|line_type| match line_type {
+            Some(line_type) => translator.line(line_type),
+            None => Ok(vec![]),
+        });
+        match translated {
+            Ok(translated) => lines.extend(translated),
+            Err(err) => issues.push((i + 1, err)),
+        }
+    }
+    let mut source = translator.target.prelude();
+    source.extend(lines);
+    let mut source = source.join("\n");
+    source.push('\n');
+    Export { source, names: translator.names, issues }
+}
+
+// None for an empty line
+pub fn parse(line: &str) -> Result<Option<LineType>, String> {
+    let tokens = post_process_paren(tokenize2(line)?)?;
+    if tokens.is_empty() {
+        return Ok(None)
+    }
+    Ok(Some(construct_line_type(tokens)?))
+}
+
+pub fn wrap((text, priority): (String, u8), needed: u8) -> String {
+    if priority < needed {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+struct Translator<T: Target> {
+    target: T,
+    names: Vec<(String, String)>,
+}
+
+impl<T: Target> Translator<T> {
+    fn line(&mut self, line_type: LineType) -> Result<Vec<String>, String> {
+        Ok(match line_type {
+            LineType::Nothing => return Err(format!("Not an instruction, it has no {} equivalent", self.target.language())),
+            LineType::Out(names) => {
+                let names = names.iter().map(|name| self.variable(name)).collect::<Result<Vec<String>, String>>()?;
+                self.target.out(names)
+            }
+            LineType::VarOperate(var, op, ex) => {
+                let expression = self.parse(ex)?;
+                let name = self.variable(&var)?;
+                if op.operator().is_some() {
+                    let text = self.expression(&expression)?.0;
+                    if let Some(line) = self.target.compound(&name, &op, &text) {
+                        return Ok(vec![line])
+                    }
+                }
+                // "x += 1" is stored as "x + 1"
+                let expression = match op.operator() {
+                    Some(o) => Possible::expression(Expression::new(Possible::token(Token::Name(var)), o, expression)),
+                    None => expression,
+                };
+                let text = self.expression(&expression)?.0;
+                vec![self.target.assign(&name, text)]
+            }
+            LineType::If(condition, actions) => {
+                let condition = self.condition(&condition)?;
+                let body = self.block(actions)?;
+                self.target.if_block(condition, body)
+            }
+            LineType::While(condition, actions) => {
+                let condition = self.condition(&condition)?;
+                let body = self.block(actions)?;
+                self.target.while_block(condition, body)
+            }
+            LineType::Solve(var, a, b) => {
+                let equation = Possible::expression(Expression::new(self.parse(a)?, Operator::Minus, self.parse(b)?));
+                let equation = self.expression(&equation)?.0;
+                let var = self.variable(&var)?;
+                self.target.solve(&var, equation)?
+            }
+        })
+    }
+
+    fn block(&mut self, actions: Vec<Vec<Token>>) -> Result<Vec<String>, String> {
+        let mut lines = Vec::new();
+        for action in actions {
+            lines.extend(self.line(construct_line_type(action)?)?);
+        }
+        Ok(lines)
+    }
+
+    fn condition(&mut self, condition: &Condition) -> Result<String, String> {
+        let first = self.parse(condition.first.clone())?;
+        let first = self.expression(&first)?.0;
+        let mut rest = Vec::new();
+        for (c, operand) in &condition.rest {
+            let operand = self.parse(operand.clone())?;
+            rest.push((c.clone(), self.expression(&operand)?.0));
+        }
+        Ok(self.target.condition(first, rest))
+    }
+
+    // Lists and matrices are stored in other variables of the calculators, they aren't supported yet
+    fn parse(&self, tokens: Vec<Token>) -> Result<Possible, String> {
+        fn has_list(tokens: &[Token]) -> bool {
+            tokens.iter().any(|token| match token {
+                Token::Paren(tokens) => has_list(tokens),
+                Token::Bracket(_) | Token::BracketOpen | Token::List(_) | Token::Matrix(_) => true,
+                _ => false,
+            })
+        }
+        if has_list(&tokens) {
+            return Err(format!("Lists and matrices can't be translated to {}", self.target.language()))
+        }
+        parse_expression(tokens)
+    }
+
+    // Text of the expression and its priority
+    fn expression(&mut self, possible: &Possible) -> Result<(String, u8), String> {
+        match possible {
+            Possible::PossToken(Token::Number(num)) => Ok(self.target.number(*num)),
+            Possible::PossToken(Token::Decimal(num)) => Ok(self.target.number(num.to_f64())),
+            Possible::PossToken(Token::Integer(num)) => Ok(self.target.number(num.to_f64())),
+            Possible::PossToken(Token::Name(name)) => Ok((self.variable(name)?, ATOM)),
+            Possible::PossToken(token) => Err(format!("{} can't be translated to {}", format_token(token), self.target.language())),
+            Possible::PossCall(function, args) => {
+                let mut texts = Vec::new();
+                for arg in args {
+                    texts.push(self.expression(arg)?.0);
+                }
+                Ok((self.target.call(function, texts)?, ATOM))
+            }
+            Possible::PossExpression(expression) => {
+                // "-a" is read as "-1 * a"
+                if expression.o == Operator::Multiply && *expression.a == Possible::PossToken(Token::Number(-1.0)) {
+                    let b = self.expression(&expression.b)?;
+                    return Ok(self.target.negation(b))
+                }
+                let a = self.expression(&expression.a)?;
+                let b = self.expression(&expression.b)?;
+                self.target.operation(&expression.o, a, b)
+            }
+        }
+    }
+
+    fn variable(&mut self, name: &str) -> Result<String, String> {
+        if let Some((_, variable)) = self.names.iter().find(|(malors, _)| malors == name) {
+            return Ok(variable.clone())
+        }
+        let variable = self.target.variable(name, &self.names)?;
+        self.names.push((name.to_string(), variable.clone()));
+        Ok(variable)
+    }
+}
+
+// A name keeps its first letter when it's free, otherwise it takes the first free variable of the calculator
+pub fn letter_variable(name: &str, taken: &[(String, String)], letters: &[&str], language: &str) -> Result<String, String> {
+    let free = |variable: &&&str| !taken.iter().any(|(_, taken)| taken == *variable);
+    let first = name.chars().next().map(|c| c.to_ascii_uppercase().to_string());
+    letters.iter().find(|letter| Some(letter.to_string()) == first && free(letter))
+        .or_else(|| letters.iter().find(free))
+        .map(|letter| letter.to_string())
+        .ok_or(format!("No variable left for [{}], {} has {} real variables", name, language, letters.len()))
+}
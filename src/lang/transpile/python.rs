@@ -0,0 +1,217 @@
+/*
+Export to Python for the MicroPython of the NumWorks and TI-Python calculators.
+Names are kept, "a // b" (log base b of a) becomes math.log(a, b) and the compound operations stay compound.
+Only the math and random modules are used, both exist on the calculators
+ */
+
+use std::f64::consts::{E, PI};
+use crate::lang::tokenizer::{Compare, Function, Operation, Operator};
+use crate::lang::transpile::{export, wrap, Export, Target, ATOM, NEGATION, POWER, PRODUCT, SUM};
+
+const INDENT: &str = "    ";
+
+// Keywords and the names the export uses, a variable named like them gets a "_" appended
+const RESERVED: &[&str] = &["False", "None", "True", "and", "as", "assert", "break", "class", "continue", "def", "del",
+    "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal",
+    "not", "or", "pass", "raise", "return", "try", "while", "with", "yield", "math", "random", "print", "range", "sum",
+    "abs", "int"];
+
+pub fn to_python(script: &str) -> Export {
+    export(script, Python { math: false, random: false })
+}
+
+struct Python {
+    math: bool, // Set when a function of the math module is used
+    random: bool,
+}
+
+impl Target for Python {
+    fn language(&self) -> &'static str {
+        "Python"
+    }
+
+    fn variable(&self, name: &str, taken: &[(String, String)]) -> Result<String, String> {
+        let mut variable = name.to_string();
+        while RESERVED.contains(&variable.as_str()) || taken.iter().any(|(_, taken)| *taken == variable) {
+            variable.push('_');
+        }
+        Ok(variable)
+    }
+
+    fn number(&mut self, num: f64) -> (String, u8) {
+        let text = if num.abs() == PI || num.abs() == E {
+            self.math = true;
+            if num.abs() == PI { "math.pi".to_string() } else { "math.e".to_string() }
+        } else if num != 0.0 && (num.abs() >= 1e16 || num.abs() < 1e-5) {
+            format!("{:e}", num.abs())
+        } else {
+            format!("{}", num.abs())
+        };
+        if num < 0.0 {
+            (format!("-{}", text), NEGATION)
+        } else {
+            (text, ATOM)
+        }
+    }
+
+    fn negation(&self, a: (String, u8)) -> (String, u8) {
+        (format!("-{}", wrap(a, POWER)), NEGATION)
+    }
+
+    fn operation(&mut self, o: &Operator, a: (String, u8), b: (String, u8)) -> Result<(String, u8), String> {
+        Ok(match o {
+            Operator::Plus => (format!("{} + {}", wrap(a, SUM), wrap(b, SUM)), SUM),
+            Operator::Minus => (format!("{} - {}", wrap(a, SUM), wrap(b, PRODUCT)), SUM),
+            Operator::Multiply => (format!("{} * {}", wrap(a, PRODUCT), wrap(b, PRODUCT)), PRODUCT),
+            Operator::Divide => (format!("{} / {}", wrap(a, PRODUCT), wrap(b, NEGATION)), PRODUCT),
+            // ** is read from right to left and before a minus sign: "-2 ** 2" is -4
+            Operator::Exponent => (format!("{} ** {}", wrap(a, ATOM), wrap(b, NEGATION)), POWER),
+            Operator::Log => {
+                self.math = true;
+                (format!("math.log({}, {})", a.0, b.0), ATOM)
+            }
+            // The % and // of Python are the modulo and the floor division of Malors
+            Operator::Modulo => (format!("{} % {}", wrap(a, PRODUCT), wrap(b, NEGATION)), PRODUCT),
+            Operator::IntDivide => (format!("{} // {}", wrap(a, PRODUCT), wrap(b, NEGATION)), PRODUCT),
+            // Bitwise operators only take integers and apply after the arithmetic ones, like in Malors
+            Operator::And => (format!("(int({}) & int({}))", a.0, b.0), ATOM),
+            Operator::Or => (format!("(int({}) | int({}))", a.0, b.0), ATOM),
+            Operator::Xor => (format!("(int({}) ^ int({}))", a.0, b.0), ATOM),
+            Operator::ShiftLeft => (format!("(int({}) << int({}))", a.0, b.0), ATOM),
+            Operator::ShiftRight => (format!("(int({}) >> int({}))", a.0, b.0), ATOM),
+            Operator::Factorial | Operator::None => return Err(format!("Operator {} can't be translated to Python", o)),
+        })
+    }
+
+    fn call(&mut self, function: &Function, args: Vec<String>) -> Result<String, String> {
+        let count = args.len();
+        Ok(match (function, count) {
+            (Function::Sin | Function::Cos | Function::Tan | Function::Asin | Function::Acos | Function::Atan
+                | Function::Sqrt | Function::Ln | Function::Exp, 1) => {
+                self.math = true;
+                let name = if *function == Function::Ln { "log".to_string() } else { function.to_string() };
+                format!("math.{}({})", name, args[0])
+            }
+            (Function::Abs, 1) => format!("abs({})", args[0]),
+            // The bound variable takes the integers from a to b
+            (Function::Sum, 4) => format!("sum({} for {} in range({}, {} + 1))", args[0], args[1], args[2], args[3]),
+            (Function::Rand, 0) => {
+                self.random = true;
+                "random.random()".to_string()
+            }
+            (Function::Randint, 2) => {
+                self.random = true;
+                format!("random.randint({}, {})", args[0], args[1])
+            }
+            // random.gauss() is missing from MicroPython, Box-Muller like Malors
+            (Function::Randn, 0) => {
+                self.math = true;
+                self.random = true;
+                "math.sqrt(-2 * math.log(1 - random.random())) * math.cos(2 * math.pi * random.random())".to_string()
+            }
+            _ => return Err(format!("{}() with {} argument(s) can't be translated to Python", function, count)),
+        })
+    }
+
+    // Python chains comparisons like Malors
+    fn condition(&self, first: String, rest: Vec<(Compare, String)>) -> String {
+        let mut condition = first;
+        for (c, b) in rest {
+            condition.push_str(&format!(" {} {}", c, b));
+        }
+        condition
+    }
+
+    fn compound(&mut self, var: &str, operation: &Operation, expression: &str) -> Option<String> {
+        match operation {
+            Operation::AddVar | Operation::SubtractVar | Operation::MultiplyVar | Operation::DivideVar | Operation::ModuloVar => {
+                Some(format!("{} {} {}", var, operation, expression))
+            }
+            _ => None,
+        }
+    }
+
+    fn assign(&self, var: &str, expression: String) -> String {
+        format!("{} = {}", var, expression)
+    }
+
+    fn out(&self, names: Vec<String>) -> Vec<String> {
+        vec![format!("print({})", names.join(", "))]
+    }
+
+    fn if_block(&self, condition: String, body: Vec<String>) -> Vec<String> {
+        block("if", condition, body)
+    }
+
+    fn while_block(&self, condition: String, body: Vec<String>) -> Vec<String> {
+        block("while", condition, body)
+    }
+
+    fn solve(&self, _var: &str, _equation: String) -> Result<Vec<String>, String> {
+        Err("solve can't be translated to Python, the calculators have no solver module".into())
+    }
+
+    fn prelude(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.math {
+            lines.push("import math".to_string());
+        }
+        if self.random {
+            lines.push("import random".to_string());
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines
+    }
+}
+
+fn block(keyword: &str, condition: String, body: Vec<String>) -> Vec<String> {
+    let mut lines = vec![format!("{} {}:", keyword, condition)];
+    lines.extend(body.into_iter().map(|line| format!("{}{}", INDENT, line)));
+    if lines.len() == 1 {
+        lines.push(format!("{}pass", INDENT));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_a_script() {
+        let export = to_python("total = 0\ni = 1\nwhile i <= 10: total += i^2: i += 1\nif total > 300: total\nr = sin(PI / 4) * -2\n");
+        assert_eq!(export.source, "import math\n\ntotal = 0\ni = 1\nwhile i <= 10:\n    total += i ** 2\n    i += 1\nif total > 300:\n    print(total)\nr = math.sin(math.pi / 4) * -2\n");
+        assert_eq!(export.names, vec![("total".to_string(), "total".to_string()), ("i".to_string(), "i".to_string()), ("r".to_string(), "r".to_string())]);
+        assert!(export.issues.is_empty());
+    }
+
+    #[test]
+    fn writes_the_priorities_of_python() {
+        assert_eq!(to_python("a = (1 - 2) * 3 ^ (4 + 5) / -a").source, "a = (1 - 2) * 3 ** (4 + 5) / -a\n");
+        assert_eq!(to_python("a = (-2) ^ 2 + 2 ^ 3 ^ 2").source, "a = (-2) ** 2 + (2 ** 3) ** 2\n");
+        assert_eq!(to_python("a = 7 % 3 + 7 div 3 + 8 // 2").source, "import math\n\na = 7 % 3 + 7 // 3 + math.log(8, 2)\n");
+        assert_eq!(to_python("a = 6 & 3 << 1").source, "a = (int(6) & int((int(3) << int(1))))\n");
+        assert_eq!(to_python("if 0 < a <= 10: a").source, "if 0 < a <= 10:\n    print(a)\n");
+    }
+
+    #[test]
+    fn renames_the_reserved_names() {
+        let export = to_python("lambda = 1\nlambda_ = 2\nmath = lambda + lambda_");
+        assert_eq!(export.source, "lambda_ = 1\nlambda__ = 2\nmath_ = lambda_ + lambda__\n");
+    }
+
+    #[test]
+    fn imports_the_modules_used() {
+        assert_eq!(to_python("a = randint(1, 6)").source, "import random\n\na = random.randint(1, 6)\n");
+        assert_eq!(to_python("a = sum(k^2, k, 1, 3)").source, "a = sum(k ** 2 for k in range(1, 3 + 1))\n");
+    }
+
+    #[test]
+    fn reports_the_lines_it_cant_translate() {
+        let export = to_python("x = 1\nsolve x: x^2 == 2\nb = x!\n");
+        assert_eq!(export.source, "x = 1\n");
+        assert_eq!(export.issues.iter().map(|(line, _)| *line).collect::<Vec<usize>>(), vec![2, 3]);
+    }
+}
@@ -0,0 +1,172 @@
+/*
+Program files .8xp of the TI-83 Plus/84 Plus, read by the link software of TI.
+The file is a 55 bytes header, one variable entry holding the TI-Basic tokens and the checksum of the entry.
+Every number is little endian
+ */
+
+const SIGNATURE: &[u8] = b"**TI83F*\x1A\x0A\x00";
+const COMMENT: &str = "Exported from Malors";
+const PROGRAM: u8 = 0x05;
+
+// Text of a token in the TI-Basic export and its bytes, the longest text is matched first
+const TOKENS: &[(&str, &[u8])] = &[
+    ("\n", &[0x3F]),
+    ("→", &[0x04]),
+    ("(", &[0x10]),
+    (")", &[0x11]),
+    (",", &[0x2B]),
+    (" ", &[0x29]),
+    (".", &[0x3A]),
+    ("ᴇ", &[0x3B]),
+    ("θ", &[0x5B]),
+    (" and ", &[0x40]),
+    (" or ", &[0x3C]),
+    (" xor ", &[0x3D]),
+    ("=", &[0x6A]),
+    ("<", &[0x6B]),
+    (">", &[0x6C]),
+    ("≤", &[0x6D]),
+    ("≥", &[0x6E]),
+    ("≠", &[0x6F]),
+    ("+", &[0x70]),
+    ("-", &[0x71]),
+    ("*", &[0x82]),
+    ("/", &[0x83]),
+    ("^", &[0xF0]),
+    ("⁻", &[0xB0]),
+    ("π", &[0xAC]),
+    ("e", &[0xBB, 0x31]),
+    ("Radian", &[0x64]),
+    ("If ", &[0xCE]),
+    ("Then", &[0xCF]),
+    ("While ", &[0xD1]),
+    ("End", &[0xD4]),
+    ("Disp ", &[0xDE]),
+    ("solve(", &[0x22]),
+    ("seq(", &[0x23]),
+    ("fnInt(", &[0x24]),
+    ("rand", &[0xAB]),
+    ("randInt(", &[0xBB, 0x0A]),
+    ("randNorm(", &[0xBB, 0x1F]),
+    ("int(", &[0xB1]),
+    ("abs(", &[0xB2]),
+    ("sum(", &[0xB6]),
+    ("prod(", &[0xB7]),
+    ("√(", &[0xBC]),
+    ("ln(", &[0xBE]),
+    ("e^(", &[0xBF]),
+    ("log(", &[0xC0]),
+    ("sin(", &[0xC2]),
+    ("sin⁻¹(", &[0xC3]),
+    ("cos(", &[0xC4]),
+    ("cos⁻¹(", &[0xC5]),
+    ("tan(", &[0xC6]),
+    ("tan⁻¹(", &[0xC7]),
+];
+
+// File of the program "name" from its TI-Basic text
+pub fn program_file(name: &str, source: &str) -> Result<Vec<u8>, String> {
+    let mut body = tokenize(source.trim_end_matches('\n'))?;
+    if body.len() > 0xFFFF - 2 {
+        return Err(format!("The program takes {} bytes, more than the calculator memory", body.len()))
+    }
+    body.splice(0..0, u16_bytes(body.len()));
+
+    // Variable entry: header size, data size, type, name on 8 bytes, version, flag and the data size again
+    let mut entry = vec![0x0D, 0x00];
+    entry.extend(u16_bytes(body.len()));
+    entry.push(PROGRAM);
+    let mut padded_name = program_name(name)?.into_bytes();
+    padded_name.resize(8, 0);
+    entry.extend(padded_name);
+    entry.extend([0x00, 0x00]);
+    entry.extend(u16_bytes(body.len()));
+    entry.extend(body);
+
+    let mut file = SIGNATURE.to_vec();
+    let mut comment = COMMENT.as_bytes().to_vec();
+    comment.resize(42, 0);
+    file.extend(comment);
+    file.extend(u16_bytes(entry.len()));
+    let checksum = entry.iter().fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+    file.extend(entry);
+    file.extend(checksum.to_le_bytes());
+    Ok(file)
+}
+
+// Names are 1 to 8 uppercase letters or digits, starting with a letter
+pub fn program_name(name: &str) -> Result<String, String> {
+    let name = name.to_uppercase();
+    let valid = !name.is_empty() && name.len() <= 8
+        && name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    if !valid {
+        return Err(format!("Invalid program name {}, it needs 1 to 8 letters or digits starting with a letter", name))
+    }
+    Ok(name)
+}
+
+// Uppercase letters and digits are their own token, the other texts are looked up in TOKENS
+fn tokenize(source: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        let longest = TOKENS.iter()
+            .filter(|(text, _)| rest.starts_with(text))
+            .max_by_key(|(text, _)| text.len());
+        if let Some((text, token)) = longest {
+            bytes.extend_from_slice(token);
+            rest = &rest[text.len()..];
+        } else if c.is_ascii_uppercase() || c.is_ascii_digit() {
+            bytes.push(c as u8);
+            rest = &rest[1..];
+        } else {
+            return Err(format!("No TI-Basic token for {}", c))
+        }
+    }
+    Ok(bytes)
+}
+
+fn u16_bytes(n: usize) -> [u8; 2] {
+    (n as u16).to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Written byte by byte from the token table of the calculator, not by program_file
+    const COUNT: &[u8] = include_bytes!("fixtures/COUNT.8xp");
+    const COUNT_SOURCE: &str = "Radian\n0→T\nWhile T<3\nT+1→T\nEnd\nIf T>2\nDisp T\n";
+
+    #[test]
+    fn writes_the_file_of_the_calculator() {
+        assert_eq!(program_file("count", COUNT_SOURCE).unwrap(), COUNT);
+    }
+
+    #[test]
+    fn sums_the_variable_entry_in_the_checksum() {
+        let file = program_file("count", COUNT_SOURCE).unwrap();
+        let entry = &file[55..file.len() - 2];
+        assert_eq!(u16::from_le_bytes([file[53], file[54]]) as usize, entry.len());
+        let checksum = entry.iter().map(|byte| *byte as u32).sum::<u32>() as u16;
+        assert_eq!(&file[file.len() - 2..], checksum.to_le_bytes());
+    }
+
+    #[test]
+    fn matches_the_longest_token() {
+        assert_eq!(tokenize("sin⁻¹(1)").unwrap(), [0xC3, 0x31, 0x11]);
+        assert_eq!(tokenize("e^(2)*e").unwrap(), [0xBF, 0x32, 0x11, 0x82, 0xBB, 0x31]);
+        assert_eq!(tokenize("A and B").unwrap(), [0x41, 0x40, 0x42]);
+        assert!(tokenize("a").is_err());
+    }
+
+    #[test]
+    fn checks_the_program_name() {
+        assert_eq!(program_name("prog1").unwrap(), "PROG1");
+        assert!(program_name("").is_err());
+        assert!(program_name("1PROG").is_err());
+        assert!(program_name("TOOLONGNAME").is_err());
+        assert!(program_name("A_B").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+/*
+Export to the TI-Basic of the TI-83/84 in its text form ("→", "⁻", "≤", ...).
+Variables become the real variables A to Z and θ, "x += 1" becomes "X+1→X"
+and a block of more than one instruction is written between Then and End
+ */
+
+use std::f64::consts::{E, PI};
+use crate::lang::tokenizer::{Compare, Function, Operator};
+use crate::lang::transpile::{export, letter_variable, wrap, Export, Target, ATOM, NEGATION, POWER, PRODUCT, SUM};
+
+const LETTERS: &[&str] = &["A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M",
+    "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "θ"];
+
+pub fn to_ti_basic(script: &str) -> Export {
+    export(script, TiBasic { radian: false })
+}
+
+struct TiBasic {
+    radian: bool, // Set when a trigonometric function is used
+}
+
+impl Target for TiBasic {
+    fn language(&self) -> &'static str {
+        "TI-Basic"
+    }
+
+    fn variable(&self, name: &str, taken: &[(String, String)]) -> Result<String, String> {
+        letter_variable(name, taken, LETTERS, self.language())
+    }
+
+    fn number(&mut self, num: f64) -> (String, u8) {
+        if num == PI {
+            return ("π".to_string(), ATOM)
+        }
+        if num == E {
+            return ("e".to_string(), ATOM)
+        }
+        // Large and tiny numbers are written with the exponent "ᴇ"
+        let text = if num != 0.0 && (num.abs() >= 1e10 || num.abs() < 1e-5) {
+            format!("{:e}", num.abs()).replace('e', "ᴇ").replace('-', "⁻")
+        } else {
+            format!("{}", num.abs())
+        };
+        if num < 0.0 {
+            (format!("⁻{}", text), NEGATION)
+        } else {
+            (text, ATOM)
+        }
+    }
+
+    fn negation(&self, a: (String, u8)) -> (String, u8) {
+        (format!("⁻{}", wrap(a, POWER)), NEGATION)
+    }
+
+    fn operation(&mut self, o: &Operator, a: (String, u8), b: (String, u8)) -> Result<(String, u8), String> {
+        Ok(match o {
+            Operator::Plus => (format!("{}+{}", wrap(a, SUM), wrap(b, SUM)), SUM),
+            Operator::Minus => (format!("{}-{}", wrap(a, SUM), wrap(b, PRODUCT)), SUM),
+            Operator::Multiply => (format!("{}*{}", wrap(a, PRODUCT), wrap(b, PRODUCT)), PRODUCT),
+            Operator::Divide => (format!("{}/{}", wrap(a, PRODUCT), wrap(b, NEGATION)), PRODUCT),
+            // The left operand of ^ is computed before a minus sign: "⁻2^2" is -4
+            Operator::Exponent => (format!("{}^{}", wrap(a, POWER), wrap(b, ATOM)), POWER),
+            Operator::Log => (format!("log({})/log({})", a.0, b.0), PRODUCT),
+            // int() is the floor, the modulo keeps the sign of the divisor like Malors
+            Operator::Modulo => {
+                let (a, b) = (wrap(a, ATOM), wrap(b, ATOM));
+                (format!("{}-{}*int({}/{})", a, b, a, b), SUM)
+            }
+            Operator::IntDivide => (format!("int({}/{})", wrap(a, PRODUCT), wrap(b, NEGATION)), ATOM),
+            Operator::ShiftLeft => (format!("{}*2^{}", wrap(a, PRODUCT), wrap(b, ATOM)), PRODUCT),
+            Operator::ShiftRight => (format!("int({}/2^{})", wrap(a, PRODUCT), wrap(b, ATOM)), ATOM),
+            Operator::And | Operator::Or | Operator::Xor => {
+                return Err(format!("Bitwise {} can't be translated, the and, or and xor of TI-Basic are logical", o))
+            }
+            Operator::Factorial | Operator::None => return Err(format!("Operator {} can't be translated to TI-Basic", o)),
+        })
+    }
+
+    fn call(&mut self, function: &Function, args: Vec<String>) -> Result<String, String> {
+        let count = args.len();
+        let args = args.join(",");
+        Ok(match (function, count) {
+            (Function::Sin | Function::Cos | Function::Tan | Function::Asin | Function::Acos | Function::Atan, 1) => {
+                self.radian = true;
+                let name = match function {
+                    Function::Sin => "sin(",
+                    Function::Cos => "cos(",
+                    Function::Tan => "tan(",
+                    Function::Asin => "sin⁻¹(",
+                    Function::Acos => "cos⁻¹(",
+                    _ => "tan⁻¹(",
+                };
+                format!("{}{})", name, args)
+            }
+            (Function::Sqrt, 1) => format!("√({})", args),
+            (Function::Ln, 1) => format!("ln({})", args),
+            (Function::Exp, 1) => format!("e^({})", args),
+            (Function::Abs, 1) => format!("abs({})", args),
+            // The bound variable is a real variable of the calculator, seq() lists the terms
+            (Function::Integrate, 4) => format!("fnInt({})", args),
+            (Function::Sum, 4) => format!("sum(seq({}))", args),
+            (Function::Prod, 4) => format!("prod(seq({}))", args),
+            (Function::Rand, 0) => "rand".to_string(),
+            (Function::Randint, 2) => format!("randInt({})", args),
+            (Function::Randn, 0) => "randNorm(0,1)".to_string(),
+            _ => return Err(format!("{}() with {} argument(s) can't be translated to TI-Basic", function, count)),
+        })
+    }
+
+    // "0 < x <= 10" => "0<X and X≤10"
+    fn condition(&self, first: String, rest: Vec<(Compare, String)>) -> String {
+        let mut a = first;
+        let mut comparisons = Vec::new();
+        for (c, b) in rest {
+            comparisons.push(format!("{}{}{}", a, compare(&c), b));
+            a = b;
+        }
+        comparisons.join(" and ")
+    }
+
+    fn assign(&self, var: &str, expression: String) -> String {
+        format!("{}→{}", expression, var)
+    }
+
+    fn out(&self, names: Vec<String>) -> Vec<String> {
+        vec![format!("Disp {}", names.join(","))]
+    }
+
+    // The short form only runs the next line, a nested block takes several
+    fn if_block(&self, condition: String, body: Vec<String>) -> Vec<String> {
+        if body.len() == 1 {
+            return vec![format!("If {}", condition), body[0].clone()]
+        }
+        let mut lines = vec![format!("If {}", condition), "Then".to_string()];
+        lines.extend(body);
+        lines.push("End".to_string());
+        lines
+    }
+
+    fn while_block(&self, condition: String, body: Vec<String>) -> Vec<String> {
+        let mut lines = vec![format!("While {}", condition)];
+        lines.extend(body);
+        lines.push("End".to_string());
+        lines
+    }
+
+    // solve(expression, variable, guess) searches a root from the guess like Malors
+    fn solve(&self, var: &str, equation: String) -> Result<Vec<String>, String> {
+        Ok(vec![format!("solve({},{},{})→{}", equation, var, var, var)])
+    }
+
+    // The trigonometric functions of Malors work in radians
+    fn prelude(&self) -> Vec<String> {
+        if self.radian { vec!["Radian".to_string()] } else { vec![] }
+    }
+}
+
+fn compare(c: &Compare) -> &'static str {
+    match c {
+        Compare::Equal => "=",
+        Compare::NotEqual => "≠",
+        Compare::LessThan => "<",
+        Compare::GreaterThan => ">",
+        Compare::LessThanOrEqual => "≤",
+        Compare::GreaterThanOrEqual => "≥",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_a_script() {
+        let export = to_ti_basic("total = 0\ni = 1\nwhile i <= 10: total += i^2: i += 1\nif total > 300: total\nr = sin(PI / 4) * -2\n");
+        assert_eq!(export.source, "Radian\n0→T\n1→I\nWhile I≤10\nT+I^2→T\nI+1→I\nEnd\nIf T>300\nDisp T\nsin(π/4)*⁻2→R\n");
+        assert_eq!(export.names, vec![("total".to_string(), "T".to_string()), ("i".to_string(), "I".to_string()), ("r".to_string(), "R".to_string())]);
+        assert!(export.issues.is_empty());
+    }
+
+    #[test]
+    fn writes_the_priorities_and_numbers_of_the_calculator() {
+        assert_eq!(to_ti_basic("a = (1 - 2) * 3 ^ (4 + 5) / -a").source, "(1-2)*3^(4+5)/⁻A→A\n");
+        assert_eq!(to_ti_basic("a = 0.000001 + 25000000000").source, "1ᴇ⁻6+2.5ᴇ10→A\n");
+        assert_eq!(to_ti_basic("a = 7 % 3").source, "7-3*int(7/3)→A\n");
+    }
+
+    #[test]
+    fn gives_the_next_free_letter() {
+        let export = to_ti_basic("a = 1\nalpha = 2\nb = alpha");
+        assert_eq!(export.source, "1→A\n2→B\nB→C\n");
+    }
+
+    #[test]
+    fn reports_the_lines_it_cant_translate() {
+        let export = to_ti_basic("a = 1\nl = [1, 2]\nb = a!\n");
+        assert_eq!(export.source, "1→A\n");
+        assert_eq!(export.issues.iter().map(|(line, _)| *line).collect::<Vec<usize>>(), vec![2, 3]);
+    }
+}
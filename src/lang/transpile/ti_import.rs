@@ -0,0 +1,534 @@
+/*
+Import of TI-83/84 programs in their TI-Basic text form ("→", "⁻", "≤", ...) as a Malors script.
+The real variables keep their letter (θ becomes theta), If/Then, While and For( blocks become one line
+and the commands Malors doesn't have (Input, Goto, strings, lists, ...) are reported and left out
+ */
+
+use crate::lang::formatter::format_line;
+use crate::lang::transpile::Export;
+
+// Piece of a TI-Basic expression with its Malors text
+#[derive(Debug, Clone, PartialEq)]
+enum Lexeme {
+    Number(f64),
+    Variable(String),
+    Symbol(&'static str),
+    Function(&'static str),
+    Compare(&'static str),
+    And,
+    Or,
+}
+
+// TI-Basic text and its lexeme, the longest text is matched first
+const LEXEMES: &[(&str, Lexeme)] = &[
+    ("+", Lexeme::Symbol("+")),
+    ("-", Lexeme::Symbol("-")),
+    ("⁻", Lexeme::Symbol("-")),
+    ("*", Lexeme::Symbol("*")),
+    ("/", Lexeme::Symbol("/")),
+    ("^", Lexeme::Symbol("^")),
+    ("²", Lexeme::Symbol("^ 2")),
+    ("⁻¹", Lexeme::Symbol("^ (-1)")),
+    ("(", Lexeme::Symbol("(")),
+    (")", Lexeme::Symbol(")")),
+    (",", Lexeme::Symbol(",")),
+    ("π", Lexeme::Symbol("PI")),
+    ("e", Lexeme::Symbol("e")),
+    ("rand", Lexeme::Symbol("rand()")),
+    ("=", Lexeme::Compare("==")),
+    ("≠", Lexeme::Compare("!=")),
+    ("<", Lexeme::Compare("<")),
+    (">", Lexeme::Compare(">")),
+    ("≤", Lexeme::Compare("<=")),
+    ("≥", Lexeme::Compare(">=")),
+    (" and ", Lexeme::And),
+    (" or ", Lexeme::Or),
+    ("sin(", Lexeme::Function("sin")),
+    ("cos(", Lexeme::Function("cos")),
+    ("tan(", Lexeme::Function("tan")),
+    ("sin⁻¹(", Lexeme::Function("asin")),
+    ("cos⁻¹(", Lexeme::Function("acos")),
+    ("tan⁻¹(", Lexeme::Function("atan")),
+    ("√(", Lexeme::Function("sqrt")),
+    ("ln(", Lexeme::Function("ln")),
+    ("e^(", Lexeme::Function("exp")),
+    ("abs(", Lexeme::Function("abs")),
+    ("log(", Lexeme::Function("log")),
+    ("int(", Lexeme::Function("int")),
+    ("fnInt(", Lexeme::Function("integrate")),
+    ("sum(", Lexeme::Function("sum")),
+    ("prod(", Lexeme::Function("prod")),
+    ("seq(", Lexeme::Function("seq")),
+    ("randInt(", Lexeme::Function("randint")),
+    ("randNorm(", Lexeme::Function("randNorm")),
+];
+
+// A statement is a line or a part of a line between ":"
+#[derive(Debug)]
+enum Statement {
+    Assign(String, String), // (expression, variable)
+    Disp(String),
+    If(String),
+    Then,
+    Else,
+    End,
+    While(String),
+    For(String),
+    Ignored,
+    Unsupported(String),
+}
+
+pub fn from_ti_basic(source: &str) -> Export {
+    let mut statements = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        for statement in split_statements(line) {
+            statements.push((i + 1, statement));
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut issues = Vec::new();
+    let mut i = 0;
+    while i < statements.len() {
+        let (number, statement) = &statements[i];
+        let (translated, next) = match statement {
+            Statement::If(condition) => match statements.get(i + 1) {
+                Some((_, Statement::Then)) => {
+                    let end = block_end(&statements, i + 2);
+                    (body(&statements, i + 2, end, "If").and_then(|body| block("if", condition, body)), end + 1)
+                }
+                Some(_) => (block("if", condition, &statements[i + 1..i + 2]), i + 2),
+                None => (Err("If without an instruction".into()), i + 1),
+            },
+            Statement::While(condition) => {
+                let end = block_end(&statements, i + 1);
+                (body(&statements, i + 1, end, "While").and_then(|body| block("wl", condition, body)), end + 1)
+            }
+            Statement::For(arguments) => {
+                let end = block_end(&statements, i + 1);
+                (body(&statements, i + 1, end, "For(").and_then(|body| for_loop(arguments, body)), end + 1)
+            }
+            statement => (simple(statement).map(|line| vec![line]), i + 1),
+        };
+        // Canonical spacing, it also checks that every line is valid Malors
+        match translated.and_then(|translated| translated.iter().map(|line| format_line(line)).collect::<Result<Vec<String>, String>>()) {
+            Ok(translated) => lines.extend(translated),
+            Err(err) => issues.push((*number, err)),
+        }
+        i = next;
+    }
+
+    let mut script = lines.join("\n");
+    script.push('\n');
+    let names = if source.contains('θ') { vec![("theta".to_string(), "θ".to_string())] } else { vec![] };
+    Export { source: script, names, issues }
+}
+
+// ":If A=1:Disp A" is two statements, a ":" inside a string doesn't split
+fn split_statements(line: &str) -> Vec<Statement> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut string = false;
+    for c in line.chars() {
+        match c {
+            '"' => string = !string,
+            '→' | '\n' => string = false,
+            ':' if !string => {
+                statements.push(statement(&current));
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    statements.push(statement(&current));
+    statements.into_iter().filter(|statement| !matches!(statement, Statement::Ignored)).collect()
+}
+
+fn statement(text: &str) -> Statement {
+    let text = text.trim_end();
+    if text.is_empty() || text == "Radian" {
+        // Malors always works in radians
+        return Statement::Ignored
+    }
+    if text.contains('"') {
+        return Statement::Unsupported("Strings can't be translated to Malors".into())
+    }
+    match text {
+        "Then" => return Statement::Then,
+        "Else" => return Statement::Else,
+        "End" => return Statement::End,
+        _ => {}
+    }
+    if let Some(condition) = text.strip_prefix("If ") {
+        return Statement::If(condition.to_string())
+    }
+    if let Some(condition) = text.strip_prefix("While ") {
+        return Statement::While(condition.to_string())
+    }
+    if let Some(arguments) = text.strip_prefix("For(") {
+        return Statement::For(arguments.trim_end_matches(')').to_string())
+    }
+    if let Some(values) = text.strip_prefix("Disp ") {
+        return Statement::Disp(values.to_string())
+    }
+    if let Some((expression, variable)) = text.rsplit_once('→') {
+        return Statement::Assign(expression.to_string(), variable.to_string())
+    }
+    let command = text.split([' ', '(']).next().unwrap_or(text);
+    Statement::Unsupported(format!("{} has no Malors equivalent", command))
+}
+
+// Index of the End closing the block starting at "start", the length of the program when it's missing
+fn block_end(statements: &[(usize, Statement)], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, (_, statement)) in statements.iter().enumerate().skip(start) {
+        match statement {
+            Statement::While(_) | Statement::For(_) => depth += 1,
+            Statement::Then => depth += 1,
+            Statement::End if depth == 0 => return i,
+            Statement::End => depth -= 1,
+            _ => {}
+        }
+    }
+    statements.len()
+}
+
+// Statements of a block from "start" to its End
+fn body<'a>(statements: &'a [(usize, Statement)], start: usize, end: usize, keyword: &str) -> Result<&'a [(usize, Statement)], String> {
+    if end >= statements.len() {
+        return Err(format!("{} without End", keyword))
+    }
+    Ok(&statements[start..end])
+}
+
+// A Malors block is one line, its actions can't be blocks
+fn block(keyword: &str, condition: &str, body: &[(usize, Statement)]) -> Result<Vec<String>, String> {
+    Ok(vec![format!("{} {}: {}", keyword, condition_text(condition)?, actions(body)?.join(": "))])
+}
+
+// For(I,1,10,2) => "I = 1" then "while I <= 10: ...: I += 2"
+fn for_loop(arguments: &str, body: &[(usize, Statement)]) -> Result<Vec<String>, String> {
+    let arguments = split_arguments(&lex(arguments)?);
+    let (variable, from, to, step) = match &arguments[..] {
+        [variable, from, to] => (variable, from, to, "1".to_string()),
+        [variable, from, to, step] => (variable, from, to, expression(step)?),
+        _ => return Err("For( needs a variable, a start, an end and an optional step".into()),
+    };
+    let variable = match &variable[..] {
+        [Lexeme::Variable(variable)] => variable.clone(),
+        _ => return Err("The variable of For( must be a real variable".into()),
+    };
+    // The direction of the loop must be known to write its condition
+    let compare = match step.replace(' ', "").parse::<f64>() {
+        Ok(step) if step > 0.0 => "<=",
+        Ok(step) if step < 0.0 => ">=",
+        _ => return Err(format!("The step {} of For( must be a non zero number", step)),
+    };
+    let mut actions = actions(body)?;
+    actions.push(format!("{} += {}", variable, step));
+    Ok(vec![
+        format!("{} = {}", variable, expression(from)?),
+        format!("while {} {} {}: {}", variable, compare, expression(to)?, actions.join(": ")),
+    ])
+}
+
+fn actions(body: &[(usize, Statement)]) -> Result<Vec<String>, String> {
+    let mut actions = Vec::new();
+    for (_, statement) in body {
+        match statement {
+            Statement::If(_) | Statement::While(_) | Statement::For(_) | Statement::Then => {
+                return Err("Nested blocks can't be written in Malors, a block is a single line".into())
+            }
+            Statement::Else => return Err("Else has no Malors equivalent".into()),
+            statement => actions.push(simple(statement)?),
+        }
+    }
+    Ok(actions)
+}
+
+// An assignment or a display
+fn simple(statement: &Statement) -> Result<String, String> {
+    match statement {
+        Statement::Assign(value, variable) => {
+            let variable = match &lex(variable)?[..] {
+                [Lexeme::Variable(variable)] => variable.clone(),
+                _ => return Err(format!("Only the real variables can be stored to, not {}", variable)),
+            };
+            Ok(format!("{} = {}", variable, expression(&lex(value)?)?))
+        }
+        // Malors only displays variables
+        Statement::Disp(values) => {
+            let mut names = Vec::new();
+            for value in split_arguments(&lex(values)?) {
+                match &value[..] {
+                    [Lexeme::Variable(variable)] => names.push(variable.clone()),
+                    _ => return Err("Disp can only be translated with variables".into()),
+                }
+            }
+            Ok(names.join(": "))
+        }
+        Statement::Then | Statement::Else | Statement::End => Err("Then, Else or End without a block".into()),
+        Statement::Unsupported(reason) => Err(reason.clone()),
+        Statement::If(_) | Statement::While(_) | Statement::For(_) | Statement::Ignored => unreachable!(),
+    }
+}
+
+// "0<X and X≤10" and "X>0 and X≤10" are the chained "0 < X <= 10", "If X" is "X != 0"
+fn condition_text(condition: &str) -> Result<String, String> {
+    let lexemes = lex(condition)?;
+    if lexemes.contains(&Lexeme::Or) {
+        return Err("or has no Malors equivalent".into())
+    }
+    if !lexemes.iter().any(|lexeme| matches!(lexeme, Lexeme::Compare(_))) && !lexemes.contains(&Lexeme::And) {
+        return Ok(format!("{} != 0", expression(&lexemes)?))
+    }
+    let mut operands: Vec<Vec<Lexeme>> = Vec::new();
+    let mut compares: Vec<&str> = Vec::new();
+    for comparison in lexemes.split(|lexeme| *lexeme == Lexeme::And) {
+        let (a, c, b) = match comparison.iter().position(|lexeme| matches!(lexeme, Lexeme::Compare(_))) {
+            Some(i) => match &comparison[i] {
+                Lexeme::Compare(c) => (comparison[..i].to_vec(), *c, comparison[i + 1..].to_vec()),
+                _ => unreachable!(),
+            },
+            None => return Err("and of values without comparison has no Malors equivalent".into()),
+        };
+        if operands.is_empty() {
+            operands = vec![a, b];
+            compares.push(c);
+            continue;
+        }
+        // "X>0 and X≤10": the first comparison is turned around to end with X
+        if compares.len() == 1 && operands[1] != a && operands[1] != b && (operands[0] == a || operands[0] == b) {
+            operands.reverse();
+            compares[0] = flip(compares[0]);
+        }
+        // Each comparison continues from the end of the chain
+        if *operands.last().unwrap() == a {
+            operands.push(b);
+            compares.push(c);
+        } else if *operands.last().unwrap() == b {
+            operands.push(a);
+            compares.push(flip(c));
+        } else {
+            return Err("and of comparisons that don't chain has no Malors equivalent".into())
+        }
+    }
+    let mut chain = expression(&operands[0])?;
+    for (c, operand) in compares.iter().zip(&operands[1..]) {
+        chain.push_str(&format!(" {} {}", c, expression(operand)?));
+    }
+    Ok(chain)
+}
+
+// "a < b" is "b > a"
+fn flip(c: &'static str) -> &'static str {
+    match c {
+        "<" => ">",
+        ">" => "<",
+        "<=" => ">=",
+        ">=" => "<=",
+        c => c,
+    }
+}
+
+fn lex(text: &str) -> Result<Vec<Lexeme>, String> {
+    let mut lexemes = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() || c == '.' {
+            let length = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+            // 1.5ᴇ⁻7 is read as "1.5e-7", parsed once to stay the closest float
+            let mut number = rest[..length].to_string();
+            rest = &rest[length..];
+            if let Some(exponent) = rest.strip_prefix('ᴇ') {
+                let negative = exponent.starts_with('⁻');
+                let exponent = exponent.trim_start_matches('⁻');
+                let length = exponent.find(|c: char| !c.is_ascii_digit()).unwrap_or(exponent.len());
+                if length == 0 {
+                    return Err("Invalid exponent after ᴇ".to_string())
+                }
+                number.push_str(&format!("e{}{}", if negative { "-" } else { "" }, &exponent[..length]));
+                rest = &exponent[length..];
+            }
+            let num: f64 = number.parse().map_err(|_| format!("Invalid number {}", number))?;
+            lexemes.push(Lexeme::Number(num));
+            continue;
+        }
+        if c.is_ascii_uppercase() {
+            lexemes.push(Lexeme::Variable(c.to_string()));
+            rest = &rest[1..];
+            continue;
+        }
+        if c == 'θ' {
+            lexemes.push(Lexeme::Variable("theta".to_string()));
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let longest = LEXEMES.iter()
+            .filter(|(text, _)| rest.starts_with(text))
+            .max_by_key(|(text, _)| text.len());
+        match longest {
+            Some((text, lexeme)) => {
+                lexemes.push(lexeme.clone());
+                rest = &rest[text.len()..];
+            }
+            None => {
+                let word: String = rest.chars().take_while(|c| !c.is_ascii_punctuation() && *c != ' ').collect();
+                return Err(format!("{} has no Malors equivalent", if word.is_empty() { c.to_string() } else { word }))
+            }
+        }
+    }
+    Ok(lexemes)
+}
+
+// Arguments separated by the commas outside of parenthesis
+fn split_arguments(lexemes: &[Lexeme]) -> Vec<Vec<Lexeme>> {
+    let mut arguments = vec![vec![]];
+    let mut depth = 0;
+    for lexeme in lexemes {
+        match lexeme {
+            Lexeme::Symbol("(") | Lexeme::Function(_) => depth += 1,
+            Lexeme::Symbol(")") => depth -= 1,
+            Lexeme::Symbol(",") if depth == 0 => {
+                arguments.push(vec![]);
+                continue;
+            }
+            _ => {}
+        }
+        arguments.last_mut().unwrap().push(lexeme.clone());
+    }
+    arguments
+}
+
+fn expression(lexemes: &[Lexeme]) -> Result<String, String> {
+    let mut i = 0;
+    let text = sequence(lexemes, &mut i)?;
+    if i < lexemes.len() {
+        return Err("Mismatched parenthesis".into())
+    }
+    Ok(text)
+}
+
+// Malors text of the lexemes until a "," or a ")" closing a call
+fn sequence(lexemes: &[Lexeme], i: &mut usize) -> Result<String, String> {
+    let mut parts = Vec::new();
+    while let Some(lexeme) = lexemes.get(*i) {
+        *i += 1;
+        match lexeme {
+            Lexeme::Symbol(")") | Lexeme::Symbol(",") => {
+                *i -= 1;
+                break;
+            }
+            Lexeme::Symbol("(") => {
+                let inner = sequence(lexemes, i)?;
+                closing(lexemes, i);
+                parts.push(format!("({})", inner));
+            }
+            Lexeme::Function(name) => {
+                // sum(seq(expression, variable, a, b)) is the sum of Malors
+                let over_sequence = matches!(*name, "sum" | "prod") && lexemes.get(*i) == Some(&Lexeme::Function("seq"));
+                if over_sequence {
+                    *i += 1;
+                }
+                let mut args = vec![sequence(lexemes, i)?];
+                while lexemes.get(*i) == Some(&Lexeme::Symbol(",")) {
+                    *i += 1;
+                    args.push(sequence(lexemes, i)?);
+                }
+                closing(lexemes, i);
+                if over_sequence {
+                    closing(lexemes, i);
+                }
+                parts.push(call(name, over_sequence, args)?);
+            }
+            Lexeme::Number(num) => parts.push(num.to_string()),
+            Lexeme::Variable(variable) => parts.push(variable.clone()),
+            Lexeme::Symbol(symbol) => parts.push(symbol.to_string()),
+            Lexeme::Compare(_) | Lexeme::And | Lexeme::Or => return Err("Comparisons are only translated in conditions".into()),
+        }
+    }
+    Ok(parts.join(" "))
+}
+
+// The closing parenthesis can be omitted at the end of a line in TI-Basic
+fn closing(lexemes: &[Lexeme], i: &mut usize) {
+    if lexemes.get(*i) == Some(&Lexeme::Symbol(")")) {
+        *i += 1;
+    }
+}
+
+fn call(name: &str, over_sequence: bool, args: Vec<String>) -> Result<String, String> {
+    Ok(match (name, &args[..]) {
+        ("sin" | "cos" | "tan" | "asin" | "acos" | "atan" | "sqrt" | "ln" | "exp" | "abs", [x]) => format!("{}({})", name, x),
+        ("log", [x]) => format!("({}) // 10", x),
+        // int( is the floor
+        ("int", [x]) => format!("({}) div 1", x),
+        ("integrate", [_, _, _, _]) => format!("integrate({})", args.join(", ")),
+        ("sum" | "prod", [_, _, _, _]) if over_sequence => format!("{}({})", name, args.join(", ")),
+        ("sum" | "prod" | "seq", _) => return Err(format!("{}( of a list has no Malors equivalent", name)),
+        ("randint", [_, _]) => format!("randint({})", args.join(", ")),
+        ("randNorm", [mean, deviation]) if mean == "0" && deviation == "1" => "randn()".to_string(),
+        ("randNorm", [mean, deviation]) => format!("(({}) + ({}) * randn())", mean, deviation),
+        _ => return Err(format!("{}( with {} argument(s) has no Malors equivalent", name, args.len())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+    use crate::lang::run_line;
+
+    #[test]
+    fn imports_a_program() {
+        let export = from_ti_basic("Radian\n0→T\nFor(I,1,10)\nT+I²→T\nEnd\nIf T>300:Then\nDisp T\nEnd\n");
+        assert_eq!(export.source, "T = 0\nI = 1\nwhile I <= 10: T = T + I ^ 2: I += 1\nif T > 300: T\n");
+        assert!(export.names.is_empty());
+        assert!(export.issues.is_empty());
+    }
+
+    #[test]
+    fn translates_the_expressions() {
+        assert_eq!(from_ti_basic("⁻2sin(π/4)→A").source, "A = -2 sin(PI / 4)\n");
+        assert_eq!(from_ti_basic("1.5ᴇ⁻7+int(A)+log(100→B").source, "B = 0.00000015 + A div 1 + 100 // 10\n");
+        assert_eq!(from_ti_basic("sum(seq(K²,K,1,4))→S").source, "S = sum(K ^ 2, K, 1, 4)\n");
+        assert_eq!(from_ti_basic("randNorm(0,1)+randNorm(5,2)→θ").source, "theta = randn() + ((5) + (2) * randn())\n");
+    }
+
+    #[test]
+    fn reads_the_exponents_exactly() {
+        assert_eq!(from_ti_basic("3ᴇ⁻1→A").source, "A = 0.3\n");
+        assert_eq!(from_ti_basic("1.1ᴇ2→A").source, "A = 110\n");
+        assert_eq!(from_ti_basic("7ᴇ⁻20→A").source, "A = 0.00000000000000000007\n");
+        assert!(lex("2ᴇ").is_err());
+    }
+
+    #[test]
+    fn chains_the_conditions() {
+        assert_eq!(from_ti_basic("If 0<X and X≤10\nDisp X").source, "if 0 < X <= 10: X\n");
+        assert_eq!(from_ti_basic("If X>0 and X≤10\nDisp X").source, "if 0 < X <= 10: X\n");
+        assert_eq!(from_ti_basic("While X\nX-1→X\nEnd").source, "while X != 0: X = X - 1\n");
+        assert_eq!(from_ti_basic("If X=1 or X=2\nDisp X").issues.len(), 1);
+    }
+
+    #[test]
+    fn reports_the_statements_malors_doesnt_have() {
+        let export = from_ti_basic("1→A\nInput B\nDisp \"HI\"\nWhile A<3\nA+1→A\n");
+        assert_eq!(export.source, "A = 1\n");
+        assert_eq!(export.issues.iter().map(|(line, _)| *line).collect::<Vec<usize>>(), vec![2, 3, 4]);
+        assert_eq!(export.issues[0].1, "Input has no Malors equivalent");
+        assert_eq!(export.issues[2].1, "While without End");
+    }
+
+    #[test]
+    fn runs_the_imported_program() {
+        let export = from_ti_basic("0→T\nFor(I,1,10)\nT+I²→T\nEnd\n");
+        let mut memory = HashMap::new();
+        for line in export.source.lines() {
+            run_line(line, &mut memory).unwrap();
+        }
+        assert_eq!(memory["T"].clone().number(), Ok(385.0));
+    }
+}
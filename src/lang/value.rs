@@ -0,0 +1,362 @@
+/*
+A value of the memory: a number, a list of numbers or a matrix stored as a list of rows.
+Operations are applied element by element, a number is applied to every element of a list or a matrix,
+except "*" with a matrix which is the matrix product.
+A decimal of $backend decimal computes with the numbers, it is a float in lists and matrices.
+With the default backend the integers are exact: + - * % div, the bitwise operators, ^ with a positive exponent and ! keep all their digits,
+the other operations and the lists and matrices take them as floats. A result above 100000 digits is a float too
+ */
+
+use std::fmt;
+use crate::lang::backend::{self, Backend};
+use crate::lang::bigint::BigInt;
+use crate::lang::decimal::Decimal;
+use crate::lang::matrix;
+use crate::lang::numeric::Numeric;
+use crate::lang::tokenizer::{self, Compare, Operator, Token};
+
+// Digits above which a power, a product or a factorial is computed as a float, the product of 10^6 digits takes seconds
+const MAX_POWER_DIGITS: usize = 100_000;
+
+#[derive(Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Decimal(Decimal),
+    Integer(BigInt),
+    List(Vec<f64>),
+    Matrix(Vec<Vec<f64>>),
+}
+
+impl Value {
+    pub fn number(self) -> Result<f64, String> {
+        match self {
+            Value::Number(num) => Ok(num),
+            Value::Decimal(num) => Ok(num.to_f64()),
+            Value::Integer(num) => Ok(num.to_f64()),
+            Value::List(_) => Err(format!("Expected a number, found the list {}", self)),
+            Value::Matrix(ref matrix) => Err(format!("Expected a number, found a {} matrix", matrix::size(matrix))),
+        }
+    }
+
+    pub fn decimal(self) -> Result<Decimal, String> {
+        match self {
+            Value::Decimal(num) => Ok(num),
+            Value::Integer(num) => Ok(Decimal::from_bigint(num)),
+            value => Decimal::from_f64(value.number()?),
+        }
+    }
+
+    // The decimals and the integers become floats
+    pub fn float(self) -> Value {
+        match self {
+            Value::Decimal(num) => Value::Number(num.to_f64()),
+            Value::Integer(num) => Value::Number(num.to_f64()),
+            value => value,
+        }
+    }
+
+    // Token the value is substituted with in an expression
+    pub fn token(self) -> Token {
+        match self {
+            Value::Number(num) => Token::Number(num),
+            Value::Decimal(num) => Token::Decimal(num),
+            Value::Integer(num) => Token::Integer(num),
+            Value::List(list) => Token::List(list),
+            Value::Matrix(matrix) => Token::Matrix(matrix),
+        }
+    }
+
+    pub fn map<F: Fn(f64) -> Result<f64, String>>(self, f: F) -> Result<Value, String> {
+        match self {
+            num @ (Value::Number(_) | Value::Decimal(_) | Value::Integer(_)) => Ok(Value::Number(f(num.number()?)?)),
+            Value::List(list) => Ok(Value::List(list.into_iter().map(f).collect::<Result<Vec<f64>, String>>()?)),
+            Value::Matrix(matrix) => Ok(Value::Matrix(matrix.into_iter()
+                .map(|row| row.into_iter().map(&f).collect::<Result<Vec<f64>, String>>())
+                .collect::<Result<Vec<Vec<f64>>, String>>()?)),
+        }
+    }
+
+    // [1, 2] + [3, 4] = [4, 6], [1, 2] * 2 = [2, 4]
+    pub fn zip_with<F: Fn(f64, f64) -> Result<f64, String>>(self, other: Value, f: F) -> Result<Value, String> {
+        match (self.float(), other.float()) {
+            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(f(a, b)?)),
+            (a, Value::Number(b)) => a.map(|a| f(a, b)),
+            (Value::Number(a), b) => b.map(|b| f(a, b)),
+            (Value::List(a), Value::List(b)) => {
+                if a.len() != b.len() {
+                    return Err(format!("Lists of different lengths: {} and {}", a.len(), b.len()))
+                }
+                Ok(Value::List(a.into_iter().zip(b).map(|(a, b)| f(a, b)).collect::<Result<Vec<f64>, String>>()?))
+            }
+            (Value::Matrix(a), Value::Matrix(b)) => {
+                if matrix::size(&a) != matrix::size(&b) {
+                    return Err(format!("Matrices of different sizes: {} and {}", matrix::size(&a), matrix::size(&b)))
+                }
+                let mut rows = Vec::new();
+                for (a, b) in a.into_iter().zip(b) {
+                    rows.push(a.into_iter().zip(b).map(|(a, b)| f(a, b)).collect::<Result<Vec<f64>, String>>()?);
+                }
+                Ok(Value::Matrix(rows))
+            }
+            (a, b) => Err(format!("Can't combine {} with {} element by element", a.kind(), b.kind())),
+        }
+    }
+
+    pub fn operate(self, o: &Operator, other: Value) -> Result<Value, String> {
+        let decimal = matches!(backend::selected(), Backend::Decimal(_));
+        match (self, o, other) {
+            // "n!" is read as "n ! 0"
+            (a, Operator::Factorial, _) => a.factorial(),
+            (a @ (Value::Number(_) | Value::Decimal(_) | Value::Integer(_)), o, b @ (Value::Number(_) | Value::Decimal(_) | Value::Integer(_)))
+                if decimal && (matches!(a, Value::Decimal(_)) || matches!(b, Value::Decimal(_))) => {
+                Ok(Value::Decimal(o.compute(a.decimal()?, b.decimal()?)?))
+            }
+            (Value::Integer(a), o, Value::Integer(b)) if backend::selected() == Backend::Double => integer_operate(a, o, b),
+            // With a float, a list, a matrix or once the backend changed
+            (a @ (Value::Decimal(_) | Value::Integer(_)), o, b) | (a, o, b @ (Value::Decimal(_) | Value::Integer(_))) => {
+                a.float().operate(o, b.float())
+            }
+            (Value::Matrix(a), Operator::Multiply, Value::Matrix(b)) => Ok(Value::Matrix(matrix::multiply(&a, &b)?)),
+            // The list is a column on the right of a matrix and a row on its left
+            (Value::Matrix(a), Operator::Multiply, Value::List(b)) => {
+                let column: Vec<Vec<f64>> = b.into_iter().map(|x| vec![x]).collect();
+                Ok(Value::List(matrix::multiply(&a, &column)?.into_iter().map(|row| row[0]).collect()))
+            }
+            (Value::List(a), Operator::Multiply, Value::Matrix(b)) => Ok(Value::List(matrix::multiply(&[a], &b)?.remove(0))),
+            // m^2 = m * m, m^-1 is the inverse
+            (Value::Matrix(a), Operator::Exponent, Value::Number(n)) => Ok(Value::Matrix(matrix::power(&a, n)?)),
+            (a, o, b) => a.zip_with(b, |a, b| o.operate(a, b)),
+        }
+    }
+
+    // Exact for the integers, with the digits of the backend for the decimals
+    fn factorial(self) -> Result<Value, String> {
+        match self {
+            Value::Integer(n) => {
+                let n = tokenizer::factorial_argument(n.to_f64())?;
+                if factorial_digits(n) > MAX_POWER_DIGITS {
+                    return Value::Number(n as f64).factorial()
+                }
+                Ok(Value::Integer(BigInt::factorial(n)))
+            }
+            Value::Decimal(n) => Ok(Value::Decimal(n.factorial()?)),
+            value => value.map(|x| Operator::Factorial.operate(x, 0.0)),
+        }
+    }
+
+    // Lists are indexed from 0, the index of a matrix is a row
+    pub fn index(&self, index: f64) -> Result<Value, String> {
+        let position = |length: usize| {
+            if index.fract() != 0.0 || index < 0.0 || index >= length as f64 {
+                return Err(format!("Invalid index {} for a {} of length {}", index, self.kind(), length))
+            }
+            Ok(index as usize)
+        };
+        match self {
+            Value::List(list) => Ok(Value::Number(list[position(list.len())?])),
+            Value::Matrix(matrix) => Ok(Value::List(matrix[position(matrix.len())?].clone())),
+            Value::Number(_) | Value::Decimal(_) | Value::Integer(_) => Err(format!("{} is not a list, it can't be indexed", self)),
+        }
+    }
+
+    // Decimals and integers are compared exactly, a float with them is taken as the decimal it's written as
+    pub fn compare(&self, c: &Compare, other: &Value) -> Result<bool, String> {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) => Ok(c.compare(a, b)),
+            (Value::Decimal(_), _) | (_, Value::Decimal(_)) => Ok(c.compare(self.clone().decimal()?, other.clone().decimal()?)),
+            _ => Ok(c.compare(self.clone().number()?, other.clone().number()?)),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Number(_) | Value::Decimal(_) | Value::Integer(_) => "number",
+            Value::List(_) => "list",
+            Value::Matrix(_) => "matrix",
+        }
+    }
+}
+
+// Operations of two exact integers, a division or a power without an integer result gives a float
+fn integer_operate(a: BigInt, o: &Operator, b: BigInt) -> Result<Value, String> {
+    match o {
+        Operator::Plus => Ok(Value::Integer(a.add(&b))),
+        Operator::Minus => Ok(Value::Integer(a.subtract(&b))),
+        Operator::Multiply if a.digits() + b.digits() <= MAX_POWER_DIGITS => Ok(Value::Integer(a.multiply(&b))),
+        Operator::Divide if !b.is_zero() => {
+            let (quotient, remainder) = a.divide(&b)?;
+            if !remainder.is_zero() {
+                return Value::Number(a.to_f64()).operate(o, Value::Number(b.to_f64()))
+            }
+            Ok(Value::Integer(quotient))
+        }
+        // Rounded down, the remainder has the sign of the divisor like the floats: -7 % 3 = 2
+        Operator::Modulo | Operator::IntDivide if !b.is_zero() => {
+            let (mut quotient, mut remainder) = a.divide(&b)?;
+            if !remainder.is_zero() && remainder.is_negative() != b.is_negative() {
+                quotient = quotient.subtract(&BigInt::from_i128(1));
+                remainder = remainder.add(&b);
+            }
+            Ok(Value::Integer(if *o == Operator::Modulo { remainder } else { quotient }))
+        }
+        Operator::Exponent if !b.is_negative() => match b.to_i64() {
+            Some(n) if (a.digits() as i64).saturating_mul(n) <= MAX_POWER_DIGITS as i64 => Ok(Value::Integer(a.pow(n as u32))),
+            _ => Value::Number(a.to_f64()).operate(o, Value::Number(b.to_f64())),
+        },
+        Operator::And => Ok(Value::Integer(a.bitwise(&b, |x, y| x & y))),
+        Operator::Or => Ok(Value::Integer(a.bitwise(&b, |x, y| x | y))),
+        Operator::Xor => Ok(Value::Integer(a.bitwise(&b, |x, y| x ^ y))),
+        Operator::ShiftLeft | Operator::ShiftRight => {
+            // 2^bits has about 0.3 * bits digits
+            let bits = match b.to_i64() {
+                Some(bits) if bits >= 0 && (*o == Operator::ShiftRight || a.digits() + bits as usize * 3 / 10 <= MAX_POWER_DIGITS) => bits,
+                _ => return Err(format!("Invalid shift of {} bits", b)),
+            };
+            if *o == Operator::ShiftLeft {
+                return Ok(Value::Integer(a.shift_left(bits as u32)))
+            }
+            Ok(Value::Integer(a.shift_right(bits.min(u32::MAX as i64) as u32)))
+        }
+        _ => Value::Number(a.to_f64()).operate(o, Value::Number(b.to_f64())),
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(num) => write!(f, "{}", number_text(*num)),
+            Value::Decimal(num) => write!(f, "{}", num),
+            Value::Integer(num) => write!(f, "{}", num),
+            Value::List(list) => {
+                let elements: Vec<String> = list.iter().map(|num| number_text(*num)).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            // One line per row, every column aligned on the right
+            Value::Matrix(matrix) => {
+                let cells: Vec<Vec<String>> = matrix.iter().map(|row| row.iter().map(|num| number_text(*num)).collect()).collect();
+                let widths: Vec<usize> = (0..cells.first().map_or(0, |row| row.len()))
+                    .map(|j| cells.iter().map(|row| row[j].len()).max().unwrap_or(0))
+                    .collect();
+                let rows: Vec<String> = cells.iter().map(|row| {
+                    let row: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:>width$}", cell, width = width)).collect();
+                    format!("[{}]", row.join("  "))
+                }).collect();
+                write!(f, "{}", rows.join("\n"))
+            }
+        }
+    }
+}
+
+// The heap is printed with {:?}, numbers stay printed as plain numbers
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(num) => write!(f, "{}", heap_text(*num)),
+            Value::Decimal(num) => write!(f, "{}", num),
+            Value::Integer(num) => write!(f, "{}", num),
+            Value::List(list) => write!(f, "{}", heap_list(list)),
+            Value::Matrix(matrix) => {
+                let rows: Vec<String> = matrix.iter().map(|row| heap_list(row)).collect();
+                write!(f, "[{}]", rows.join(", "))
+            }
+        }
+    }
+}
+
+// log10(n!) = log10(2) + ... + log10(n)
+fn factorial_digits(n: u32) -> usize {
+    (2..=n).map(|k| (k as f64).log10()).sum::<f64>() as usize + 1
+}
+
+fn number_text(num: f64) -> String {
+    backend::selected().display(num)
+}
+
+// The floats keep their "1.0" in the heap, unless the backend shows fewer digits than it computes
+fn heap_text(num: f64) -> String {
+    match backend::selected() {
+        backend @ (Backend::Single | Backend::Calculator) => backend.display(num),
+        _ => format!("{:?}", num),
+    }
+}
+
+fn heap_list(list: &[f64]) -> String {
+    let elements: Vec<String> = list.iter().map(|num| heap_text(*num)).collect();
+    format!("[{}]", elements.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+    use crate::lang::run_line;
+
+    fn list(elements: &[f64]) -> Value {
+        Value::List(elements.to_vec())
+    }
+
+    // The value a script leaves in r, or its error
+    fn result(script: &str) -> Result<String, String> {
+        let mut memory = HashMap::new();
+        for line in script.lines() {
+            run_line(line, &mut memory)?;
+        }
+        Ok(memory["r"].to_string())
+    }
+
+    #[test]
+    fn indexes_the_lists() {
+        let l = list(&[1.0, 2.0, 3.0]);
+        assert!(l.index(0.0) == Ok(Value::Number(1.0)));
+        assert!(l.index(2.0) == Ok(Value::Number(3.0)));
+        assert_eq!(l.index(3.0).err(), Some("Invalid index 3 for a list of length 3".to_string()));
+        assert_eq!(l.index(-1.0).err(), Some("Invalid index -1 for a list of length 3".to_string()));
+        assert_eq!(l.index(0.5).err(), Some("Invalid index 0.5 for a list of length 3".to_string()));
+        assert!(Value::Number(1.0).index(0.0).is_err());
+        let m = Value::Matrix(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        assert!(m.index(1.0) == Ok(list(&[3.0, 4.0])));
+        assert!(m.index(2.0).is_err());
+    }
+
+    #[test]
+    fn runs_the_lists() {
+        assert_eq!(result("l = [1, 2, 3]\nr = l[1]"), Ok("2".to_string()));
+        assert_eq!(result("l = [1, 2, 3]\nr = l[len(l) - 1]"), Ok("3".to_string()));
+        assert!(result("l = [1, 2, 3]\nr = l[3]").is_err());
+        assert!(result("l = [1, 2, 3]\nr = l[-1]").is_err());
+        assert_eq!(result("l = [1, 2]\nr = append(l, 3)"), Ok("[1, 2, 3]".to_string()));
+        assert_eq!(result("l = append([], 5)\nr = len(l)"), Ok("1".to_string()));
+        assert_eq!(result("r = len([])"), Ok("0".to_string()));
+        assert_eq!(result("r = len([4, 5, 6])"), Ok("3".to_string()));
+        assert_eq!(result("m = [[1, 2], [3, 4]]\nr = m[1][0]"), Ok("3".to_string()));
+    }
+
+    #[test]
+    fn operates_element_by_element() {
+        assert!(list(&[1.0, 2.0]).operate(&Operator::Plus, list(&[3.0, 4.0])) == Ok(list(&[4.0, 6.0])));
+        assert!(list(&[1.0, 2.0]).operate(&Operator::Multiply, Value::Number(2.0)) == Ok(list(&[2.0, 4.0])));
+        assert!(Value::Number(1.0).operate(&Operator::Minus, list(&[1.0, 2.0])) == Ok(list(&[0.0, -1.0])));
+        assert_eq!(list(&[1.0, 2.0]).operate(&Operator::Plus, list(&[1.0, 2.0, 3.0])).err(),
+                   Some("Lists of different lengths: 2 and 3".to_string()));
+        assert_eq!(result("l = [1, 2]\nr = l * [1]"), Err("Lists of different lengths: 2 and 1".to_string()));
+        assert_eq!(result("l = [1, 2]\nr = 2l + [1, 1]"), Ok("[3, 5]".to_string()));
+    }
+
+    #[test]
+    fn computes_the_large_products_as_floats() {
+        let x = Value::Integer(BigInt::factorial(10000));
+        let square = x.clone().operate(&Operator::Multiply, x.clone()).unwrap();
+        assert!(matches!(square, Value::Integer(_)));
+        // 3 * 35660 digits are above the limit
+        assert!(square.operate(&Operator::Multiply, x) == Ok(Value::Number(f64::INFINITY)));
+        assert_eq!(factorial_digits(0), 1);
+        assert_eq!(factorial_digits(20), 19);
+        assert_eq!(factorial_digits(10000), 35660);
+    }
+
+    #[test]
+    fn maps_the_numbers() {
+        assert!(Value::Integer(BigInt::from_i128(4)).map(|x| Ok(x.sqrt())) == Ok(Value::Number(2.0)));
+        assert!(list(&[1.0, 4.0]).map(|x| Ok(x.sqrt())) == Ok(list(&[1.0, 2.0])));
+    }
+}
@@ -1,20 +1,220 @@
 use std::collections::HashMap;
 use  std::env;
+use std::fs;
 use std::fs::File;
 use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process;
 use crate::cli::cli;
+use crate::debugger::debugger;
+use crate::lang::checker::check_script;
+use crate::lang::formatter::format_script;
+use crate::lang::minifier::minify_script;
+use crate::lang::random::seed;
+use crate::lang::backend::{select, Backend};
+use crate::lang::transpile::casio_basic::to_casio_basic;
+use crate::lang::transpile::python::to_python;
+use crate::lang::transpile::ti_8xp::program_file;
+use crate::lang::transpile::ti_basic::to_ti_basic;
+use crate::lang::transpile::ti_import::from_ti_basic;
+use crate::lang::value::Value;
+use crate::script::script;
 
 mod cli;
+mod debugger;
 mod lang;
+mod script;
 
 fn main() {
     // Initiate heap memory
-    let mut memory:HashMap<String, f64> = HashMap::new();
+    let mut memory:HashMap<String, Value> = HashMap::new();
 
-    println!("Malors = Mathematic Logic from Rust.simplify()");
     // Get the command-line arguments
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // malors --seed <n> ... makes rand() reproducible
+    if let Some(i) = args.iter().position(|arg| arg == "--seed") {
+        match args.get(i + 1).and_then(|n| n.parse::<u64>().ok()) {
+            Some(n) => seed(n),
+            None => {
+                println!("\x1b[31mERROR:\x1b[0m --seed needs a positive integer");
+                process::exit(1)
+            }
+        }
+        args.drain(i..=i + 1);
+    }
+
+    // malors --backend <double|single|fixed|calculator|decimal:<digits>> ... changes the arithmetic
+    if let Some(i) = args.iter().position(|arg| arg == "--backend") {
+        match args.get(i + 1).map(|name| Backend::parse(name)) {
+            Some(Ok(backend)) => select(backend),
+            Some(Err(err)) => {
+                println!("\x1b[31mERROR:\x1b[0m {}", err);
+                process::exit(1)
+            }
+            None => {
+                println!("\x1b[31mERROR:\x1b[0m --backend needs double, single, fixed, calculator or decimal:<digits>");
+                process::exit(1)
+            }
+        }
+        args.drain(i..=i + 1);
+    }
 
     // Check if there are no arguments (other than the program name)
-    cli(&mut memory)
-}
\ No newline at end of file
+    match args.get(1).map(|arg| arg.as_str()) {
+        None => {
+            banner();
+            cli(&mut memory)
+        }
+        // Tools working on the source of a script
+        Some("fmt") => fmt(&args[2..]),
+        Some("minify") => minify(&args[2..]),
+        Some("check") => check(args.get(2)),
+        Some("export") => export(&args[2..]),
+        Some("import") => import(&args[2..]),
+        Some("debug") => {
+            banner();
+            debugger(&read_script(args.get(2)), &mut memory)
+        }
+        Some(_) => {
+            banner();
+            script(&read_script(args.get(1)), &mut memory)
+        }
+    }
+}
+
+fn banner() {
+    println!("Malors = Mathematic Logic from Rust.simplify()");
+}
+
+// malors fmt [--check] <file>
+fn fmt(args: &[String]) {
+    let check = args.iter().any(|arg| arg == "--check");
+    let path = args.iter().find(|arg| *arg != "--check");
+    let content = read_script(path);
+    let formatted = match format_script(&content) {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            println!("\x1b[31mPROGRAM ERROR:\x1b[0m \n{}", err);
+            process::exit(1)
+        }
+    };
+    if check {
+        if formatted != content {
+            println!("{} is not formatted", path.unwrap());
+            process::exit(1)
+        }
+    } else if formatted != content {
+        if let Err(err) = fs::write(path.unwrap(), formatted) {
+            println!("\x1b[31mERROR:\x1b[0m Unable to write {}: {}", path.unwrap(), err);
+            process::exit(1)
+        }
+    }
+}
+
+fn read_script(path: Option<&String>) -> String {
+    let path = match path {
+        None => {
+            println!("\x1b[31mERROR:\x1b[0m No script file given");
+            process::exit(1)
+        }
+        Some(path) => path
+    };
+    let mut content = String::new();
+    if let Err(err) = File::open(path).and_then(|mut file| file.read_to_string(&mut content)) {
+        println!("\x1b[31mERROR:\x1b[0m Unable to read {}: {}", path, err);
+        process::exit(1)
+    }
+    content
+}
+
+// malors minify [--keep-names] <file>
+fn minify(args: &[String]) {
+    let rename = !args.iter().any(|arg| arg == "--keep-names");
+    let path = args.iter().find(|arg| *arg != "--keep-names");
+    let content = read_script(path);
+    let minified = match minify_script(&content, rename) {
+        Ok(minified) => minified,
+        Err(err) => {
+            println!("\x1b[31mPROGRAM ERROR:\x1b[0m \n{}", err);
+            process::exit(1)
+        }
+    };
+    print!("{}", minified);
+    // The report goes to stderr so the script can be redirected to a file
+    let before = content.chars().count();
+    let after = minified.chars().count();
+    eprintln!("{} -> {} characters, {} saved ({:.1}%)", before, after, before as i64 - after as i64,
+              100.0 * (before as f64 - after as f64) / before.max(1) as f64);
+}
+
+// malors check <file>
+fn check(path: Option<&String>) {
+    let content = read_script(path);
+    let warnings = check_script(&content);
+    for (number, warning) in &warnings {
+        println!("\x1b[33mWARNING (line {}):\x1b[0m {}", number, warning);
+    }
+    if warnings.is_empty() {
+        println!("No issues found");
+    } else {
+        println!("{} issue(s) found", warnings.len());
+        process::exit(1)
+    }
+}
+
+// malors export <target> <file>, malors export 8xp <file> [<output.8xp>]
+fn export(args: &[String]) {
+    let content = read_script(args.get(1));
+    let export = match args.first().map(|target| target.as_str()) {
+        Some("ti") | Some("8xp") => to_ti_basic(&content),
+        Some("casio") => to_casio_basic(&content),
+        Some("python") => to_python(&content),
+        _ => {
+            println!("\x1b[31mERROR:\x1b[0m Unknown export target, expected: ti, 8xp, casio, python");
+            process::exit(1)
+        }
+    };
+    if args[0] == "8xp" {
+        // The program is named after the output file: PROG.8xp is the program PROG
+        let output = args.get(2).map(PathBuf::from).unwrap_or_else(|| Path::new(&args[1]).with_extension("8xp"));
+        let name = output.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        if let Err(err) = program_file(&name, &export.source).and_then(|file| fs::write(&output, file).map_err(|err| err.to_string())) {
+            println!("\x1b[31mERROR:\x1b[0m Unable to write {}: {}", output.display(), err);
+            process::exit(1)
+        }
+    } else {
+        print!("{}", export.source);
+    }
+    // The report goes to stderr so the program can be redirected to a file
+    for (malors, name) in &export.names {
+        if !malors.eq_ignore_ascii_case(name) {
+            eprintln!("{} -> {}", malors, name);
+        }
+    }
+    for (number, issue) in &export.issues {
+        eprintln!("\x1b[33mNOT TRANSLATED (line {}):\x1b[0m {}", number, issue);
+    }
+    if !export.issues.is_empty() {
+        process::exit(1)
+    }
+}
+
+// malors import ti <file>
+fn import(args: &[String]) {
+    if args.first().map(|source| source.as_str()) != Some("ti") {
+        println!("\x1b[31mERROR:\x1b[0m Unknown import source, expected: ti");
+        process::exit(1)
+    }
+    let import = from_ti_basic(&read_script(args.get(1)));
+    print!("{}", import.source);
+    for (malors, name) in &import.names {
+        eprintln!("{} -> {}", name, malors);
+    }
+    for (number, issue) in &import.issues {
+        eprintln!("\x1b[33mNOT TRANSLATED (line {}):\x1b[0m {}", number, issue);
+    }
+    if !import.issues.is_empty() {
+        process::exit(1)
+    }
+}
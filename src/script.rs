@@ -0,0 +1,16 @@
+use std::collections::HashMap;
+use crate::lang::LineResult::*;
+use crate::lang::run_line;
+use crate::lang::value::Value;
+
+pub fn script(script: &str, memory: &mut HashMap<String, Value>) {
+    for (i, line) in script.lines().enumerate() {
+        let result = run_line(line, memory);
+        if let Ok(Output(string)) = result {
+            println!("{}", string)
+        } else if let Err(err) = result {
+            println!("\x1b[31mPROGRAM ERROR (line {}):\x1b[0m \n{}", i + 1, err);
+            println!("<Instruction skipped>");
+        }
+    }
+}